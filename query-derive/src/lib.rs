@@ -0,0 +1,278 @@
+use convert_case::{Case, Casing};
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Fields, GenericArgument,
+    LitBool, LitStr, PathArguments, Type,
+};
+
+/// Implements `query::schema::Queryable`, returning a schema with a field for every field of the
+/// struct.
+///
+/// Each field is filterable with every condition and uses the struct field's name as its column.
+/// The field's type is taken from the Rust type, `Option<T>` uses the type of `T` and types that
+/// aren't recognised are strings.
+///
+/// Struct attributes:
+/// - `#[query(rename_all = "camelCase")]` renames every field, eg `user_id` becomes `userId`.
+/// - `#[query(table = "orders")]` sets the table of every field.
+///
+/// Field attributes:
+/// - `rename = "..."` sets the name used in the url query.
+/// - `column = "..."` and `table = "..."` set the SQL column and its table.
+/// - `sortable` and `groupable` allow sorting and grouping by the field.
+/// - `required` requires the url query to filter by the field.
+/// - `filterable = false` doesn't allow any condition.
+/// - `ops = "eq,in"` only allows the given conditions.
+/// - `skip` leaves the field out of the schema.
+#[proc_macro_derive(Queryable, attributes(query))]
+pub fn derive_queryable(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    match expand(input) {
+        Ok(tokens) => tokens.into(),
+        Err(err) => err.to_compile_error().into(),
+    }
+}
+
+#[derive(Default)]
+struct StructAttrs {
+    rename_all: Option<Case>,
+    table: Option<String>,
+}
+
+#[derive(Default)]
+struct FieldAttrs {
+    rename: Option<String>,
+    column: Option<String>,
+    table: Option<String>,
+    sortable: bool,
+    groupable: bool,
+    required: bool,
+    filterable: Option<bool>,
+    ops: Option<Vec<TokenStream2>>,
+    skip: bool,
+}
+
+fn expand(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => Err(Error::new(
+                input.span(),
+                "Queryable can only be derived for structs with named fields",
+            ))?,
+        },
+        _ => Err(Error::new(
+            input.span(),
+            "Queryable can only be derived for structs",
+        ))?,
+    };
+
+    let struct_attrs = parse_struct_attrs(&input)?;
+
+    let mut specs = Vec::new();
+    for field in fields {
+        let attrs = parse_field_attrs(field)?;
+        if attrs.skip {
+            continue;
+        }
+
+        let ident = field.ident.as_ref().unwrap().to_string();
+        let ident = ident.strip_prefix("r#").unwrap_or(&ident);
+
+        let name = match (attrs.rename, struct_attrs.rename_all) {
+            (Some(rename), _) => rename,
+            (None, Some(case)) => ident.to_case(case),
+            (None, None) => ident.to_owned(),
+        };
+        let column = attrs.column.unwrap_or_else(|| ident.to_owned());
+        let field_type = field_type(&field.ty);
+        let sortable = attrs.sortable;
+        let groupable = attrs.groupable;
+        let required = attrs.required;
+
+        let mut spec = quote! {
+            ::query::schema::FieldSpec::new(#name, #field_type)
+                .sortable(#sortable)
+                .groupable(#groupable)
+                .required(#required)
+                .column(#column)
+        };
+
+        if let Some(table) = attrs.table.or_else(|| struct_attrs.table.clone()) {
+            spec.extend(quote!(.table(#table)));
+        }
+
+        match (attrs.filterable, attrs.ops) {
+            (Some(false), _) => {
+                spec.extend(quote!(.conditions([] as [::query::filter::Condition; 0])));
+            }
+            (_, Some(ops)) => spec.extend(quote!(.conditions([#(#ops),*]))),
+            _ => {}
+        }
+
+        specs.push(spec);
+    }
+
+    let ident = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    Ok(quote! {
+        impl #impl_generics ::query::schema::Queryable for #ident #ty_generics #where_clause {
+            fn schema() -> ::query::schema::Schema {
+                ::query::schema::Schema::new()
+                    #(.field(#specs))*
+            }
+        }
+    })
+}
+
+fn parse_struct_attrs(input: &DeriveInput) -> Result<StructAttrs, Error> {
+    let mut attrs = StructAttrs::default();
+
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("query")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename_all") {
+                let lit: LitStr = meta.value()?.parse()?;
+                attrs.rename_all = Some(parse_case(&lit)?);
+            } else if meta.path.is_ident("table") {
+                let lit: LitStr = meta.value()?.parse()?;
+                attrs.table = Some(lit.value());
+            } else {
+                Err(meta.error("expected `rename_all` or `table`"))?
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(attrs)
+}
+
+fn parse_field_attrs(field: &syn::Field) -> Result<FieldAttrs, Error> {
+    let mut attrs = FieldAttrs::default();
+
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("query")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("rename") {
+                let lit: LitStr = meta.value()?.parse()?;
+                attrs.rename = Some(lit.value());
+            } else if meta.path.is_ident("column") {
+                let lit: LitStr = meta.value()?.parse()?;
+                attrs.column = Some(lit.value());
+            } else if meta.path.is_ident("table") {
+                let lit: LitStr = meta.value()?.parse()?;
+                attrs.table = Some(lit.value());
+            } else if meta.path.is_ident("sortable") {
+                attrs.sortable = true;
+            } else if meta.path.is_ident("groupable") {
+                attrs.groupable = true;
+            } else if meta.path.is_ident("required") {
+                attrs.required = true;
+            } else if meta.path.is_ident("filterable") {
+                // `filterable` on its own is the default
+                attrs.filterable = match meta.value() {
+                    Ok(value) => Some(value.parse::<LitBool>()?.value),
+                    Err(_) => Some(true),
+                };
+            } else if meta.path.is_ident("ops") {
+                let lit: LitStr = meta.value()?.parse()?;
+                attrs.ops = Some(parse_ops(&lit)?);
+            } else if meta.path.is_ident("skip") {
+                attrs.skip = true;
+            } else {
+                Err(meta.error(
+                    "expected one of `rename`, `column`, `table`, `sortable`, `groupable`, \
+                     `required`, `filterable`, `ops` or `skip`",
+                ))?
+            }
+
+            Ok(())
+        })?;
+    }
+
+    Ok(attrs)
+}
+
+fn parse_case(lit: &LitStr) -> Result<Case, Error> {
+    match lit.value().as_str() {
+        "camelCase" => Ok(Case::Camel),
+        "PascalCase" => Ok(Case::Pascal),
+        "snake_case" => Ok(Case::Snake),
+        "SCREAMING_SNAKE_CASE" => Ok(Case::ScreamingSnake),
+        "kebab-case" => Ok(Case::Kebab),
+        "lowercase" => Ok(Case::Flat),
+        "UPPERCASE" => Ok(Case::UpperFlat),
+        _ => Err(Error::new(lit.span(), "unsupported case")),
+    }
+}
+
+// ops = "eq,ne,in" -> [Condition::EQ, Condition::NE, Condition::IN]
+fn parse_ops(lit: &LitStr) -> Result<Vec<TokenStream2>, Error> {
+    let mut ops = Vec::new();
+    for op in lit.value().split(',').map(str::trim) {
+        let variant = match op {
+            "eq" => "EQ",
+            "ne" => "NE",
+            "gt" => "GT",
+            "ge" => "GE",
+            "lt" => "LT",
+            "le" => "LE",
+            "in" => "IN",
+            "nin" => "NIN",
+            "like" => "LIKE",
+            "ilike" => "ILIKE",
+            "sw" => "SW",
+            "ew" => "EW",
+            "ct" => "CT",
+            "null" => "NULL",
+            "notnull" => "NOTNULL",
+            "between" => "BETWEEN",
+            _ => Err(Error::new(
+                lit.span(),
+                format!("unknown condition `{}`", op),
+            ))?,
+        };
+
+        let variant = syn::Ident::new(variant, Span::call_site());
+        ops.push(quote!(::query::filter::Condition::#variant));
+    }
+
+    Ok(ops)
+}
+
+// Returns the FieldType for the last segment of the type's path, eg `chrono::NaiveDate` is a date.
+fn field_type(ty: &Type) -> TokenStream2 {
+    let segment = match ty {
+        Type::Path(path) => path.path.segments.last(),
+        Type::Reference(reference) => return field_type(&reference.elem),
+        _ => None,
+    };
+
+    let Some(segment) = segment else {
+        return quote!(::query::schema::FieldType::String);
+    };
+
+    match segment.ident.to_string().as_str() {
+        "Option" => match segment.arguments {
+            PathArguments::AngleBracketed(ref args) => match args.args.first() {
+                Some(GenericArgument::Type(ty)) => field_type(ty),
+                _ => quote!(::query::schema::FieldType::String),
+            },
+            _ => quote!(::query::schema::FieldType::String),
+        },
+        "i8" | "i16" | "i32" | "i64" | "isize" | "u8" | "u16" | "u32" | "u64" | "usize" => {
+            quote!(::query::schema::FieldType::Int)
+        }
+        "f32" | "f64" | "Decimal" | "BigDecimal" => quote!(::query::schema::FieldType::Float),
+        "bool" => quote!(::query::schema::FieldType::Bool),
+        "Uuid" => quote!(::query::schema::FieldType::Uuid),
+        "NaiveDate" | "Date" => quote!(::query::schema::FieldType::Date),
+        "DateTime" | "NaiveDateTime" | "OffsetDateTime" | "PrimitiveDateTime" => {
+            quote!(::query::schema::FieldType::Timestamp)
+        }
+        _ => quote!(::query::schema::FieldType::String),
+    }
+}
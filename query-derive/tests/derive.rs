@@ -0,0 +1,99 @@
+use query::{
+    filter::Condition,
+    schema::{FieldSpec, FieldType, Queryable, Schema},
+    sql::QueryBuilder,
+    value::Value,
+    ErrorKind, UrlQuery,
+};
+use query_derive::Queryable;
+
+#[allow(dead_code)]
+#[derive(Queryable)]
+#[query(rename_all = "camelCase", table = "orders")]
+struct Order {
+    #[query(sortable, ops = "eq,in")]
+    id: i64,
+    #[query(rename = "customer", column = "customer_id", required)]
+    user_id: Option<i64>,
+    #[query(sortable, groupable, table = "items")]
+    unit_price: f64,
+    shipped: bool,
+    #[query(filterable = false, sortable)]
+    created_at: String,
+    #[query(skip)]
+    internal_note: String,
+}
+
+#[test]
+fn test_derive_schema() {
+    let expected = Schema::new()
+        .field(
+            FieldSpec::new("id", FieldType::Int)
+                .sortable(true)
+                .column("id")
+                .table("orders")
+                .conditions([Condition::EQ, Condition::IN]),
+        )
+        .field(
+            FieldSpec::new("customer", FieldType::Int)
+                .required(true)
+                .column("customer_id")
+                .table("orders"),
+        )
+        .field(
+            FieldSpec::new("unitPrice", FieldType::Float)
+                .sortable(true)
+                .groupable(true)
+                .column("unit_price")
+                .table("items"),
+        )
+        .field(
+            FieldSpec::new("shipped", FieldType::Bool)
+                .column("shipped")
+                .table("orders"),
+        )
+        .field(
+            FieldSpec::new("createdAt", FieldType::String)
+                .sortable(true)
+                .column("created_at")
+                .table("orders")
+                .conditions([]),
+        );
+
+    assert_eq!(Order::schema(), expected);
+}
+
+#[test]
+fn test_derive_query() {
+    let schema = Order::schema();
+
+    let query = "customer=5&filter[]=id-in-1,2&sort=unitPrice-desc";
+    let parsed = UrlQuery::with_schema(query, &schema).unwrap();
+
+    let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+        .schema(&schema)
+        .build()
+        .unwrap();
+
+    assert_eq!(
+        sql,
+        "SELECT * FROM orders \
+         WHERE \"orders\".\"customer_id\" = $1 AND \"orders\".\"id\" IN ($2, $3) \
+         ORDER BY \"items\".\"unit_price\" DESC"
+    );
+    assert_eq!(args, [Value::I64(5), Value::I64(1), Value::I64(2)]);
+
+    for (query, kind) in [
+        (
+            "customer=1&filter[]=id-gt-1",
+            ErrorKind::ConditionNotAllowed,
+        ),
+        ("id=1", ErrorKind::Required),
+        ("shipped=yes", ErrorKind::InvalidValue),
+        ("internalNote=x", ErrorKind::InvalidField),
+        ("createdAt=x", ErrorKind::ConditionNotAllowed),
+    ] {
+        let err = UrlQuery::with_schema(query, &schema).unwrap_err();
+        assert_eq!(err.kind, kind, "{}", query);
+    }
+}
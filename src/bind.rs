@@ -0,0 +1,357 @@
+use sqlx::error::BoxDynError;
+#[cfg(feature = "sqlx-postgres")]
+use sqlx::types::{
+    chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime},
+    Uuid,
+};
+#[cfg(any(
+    feature = "sqlx-mysql",
+    feature = "sqlx-postgres",
+    feature = "sqlx-sqlite"
+))]
+use sqlx::{Arguments, Encode, Type};
+
+use crate::{dialect::Database, value::Value};
+
+/// A sqlx database that [`Value`]s can be bound to, used by
+/// [`QueryBuilder::build_sqlx`](crate::sql::QueryBuilder::build_sqlx).
+///
+/// Uuids, dates and timestamps are bound as text on SQLite and MySQL, which compare them with their
+/// columns as is. Postgres won't compare a `uuid`, `date` or `timestamptz` column with text, so
+/// they're bound as a `uuid`, a `date` and a `timestamptz`, or a `timestamp` without an offset.
+pub trait BindValue: sqlx::Database {
+    /// The dialect to generate the SQL with.
+    const DATABASE: Database;
+
+    /// Adds the value to the end of the arguments.
+    fn bind_value(args: &mut Self::Arguments<'static>, value: Value) -> Result<(), BoxDynError>;
+}
+
+// Binds every value except lists, which only Postgres can bind
+#[cfg(any(
+    feature = "sqlx-mysql",
+    feature = "sqlx-postgres",
+    feature = "sqlx-sqlite"
+))]
+fn bind_scalar<DB>(args: &mut DB::Arguments<'static>, value: Value) -> Result<(), BoxDynError>
+where
+    DB: sqlx::Database,
+    bool: Encode<'static, DB> + Type<DB>,
+    i64: Encode<'static, DB> + Type<DB>,
+    f64: Encode<'static, DB> + Type<DB>,
+    String: Encode<'static, DB> + Type<DB>,
+    Option<String>: Encode<'static, DB> + Type<DB>,
+{
+    match value {
+        Value::Null => args.add(None::<String>),
+        Value::Bool(value) => args.add(value),
+        Value::I64(value) => args.add(value),
+        Value::F64(value) => args.add(value),
+        Value::String(value)
+        | Value::Uuid(value)
+        | Value::Date(value)
+        | Value::Timestamp(value) => args.add(value),
+        Value::List(_) => Err(format!("{} can't bind a list", DB::NAME).into()),
+    }
+}
+
+#[cfg(feature = "sqlx-sqlite")]
+impl BindValue for sqlx::Sqlite {
+    const DATABASE: Database = Database::Sqlite;
+
+    fn bind_value(args: &mut Self::Arguments<'static>, value: Value) -> Result<(), BoxDynError> {
+        bind_scalar::<Self>(args, value)
+    }
+}
+
+#[cfg(feature = "sqlx-mysql")]
+impl BindValue for sqlx::MySql {
+    const DATABASE: Database = Database::MySQL;
+
+    fn bind_value(args: &mut Self::Arguments<'static>, value: Value) -> Result<(), BoxDynError> {
+        bind_scalar::<Self>(args, value)
+    }
+}
+
+#[cfg(feature = "sqlx-postgres")]
+impl BindValue for sqlx::Postgres {
+    const DATABASE: Database = Database::Postgres;
+
+    fn bind_value(args: &mut Self::Arguments<'static>, value: Value) -> Result<(), BoxDynError> {
+        match value {
+            Value::Uuid(value) => args.add(Uuid::parse_str(&value)?),
+            Value::Date(value) => args.add(date(&value)?),
+            Value::Timestamp(value) => match timestamp(&value)? {
+                Timestamp::Offset(value) => args.add(value),
+                Timestamp::Naive(value) => args.add(value),
+            },
+            // The values of a list all have the type of its field
+            Value::List(values) => match values.first() {
+                Some(Value::I64(_)) => args.add(list(values, |v| match v {
+                    Value::I64(v) => Some(Ok(v)),
+                    _ => None,
+                })?),
+                Some(Value::F64(_)) => args.add(list(values, |v| match v {
+                    Value::F64(v) => Some(Ok(v)),
+                    _ => None,
+                })?),
+                Some(Value::Bool(_)) => args.add(list(values, |v| match v {
+                    Value::Bool(v) => Some(Ok(v)),
+                    _ => None,
+                })?),
+                Some(Value::Uuid(_)) => args.add(list(values, |v| match v {
+                    Value::Uuid(v) => Some(Uuid::parse_str(&v).map_err(Into::into)),
+                    _ => None,
+                })?),
+                Some(Value::Date(_)) => args.add(list(values, |v| match v {
+                    Value::Date(v) => Some(date(&v)),
+                    _ => None,
+                })?),
+                Some(Value::Timestamp(_)) => {
+                    let timestamps = list(values, |v| match v {
+                        Value::Timestamp(v) => Some(timestamp(&v)),
+                        _ => None,
+                    })?;
+
+                    // An array has one type, so either every timestamp has an offset or none do
+                    match timestamps.first() {
+                        Some(Timestamp::Offset(_)) => args.add(list(timestamps, |v| match v {
+                            Timestamp::Offset(v) => Some(Ok(v)),
+                            Timestamp::Naive(_) => None,
+                        })?),
+                        _ => args.add(list(timestamps, |v| match v {
+                            Timestamp::Naive(v) => Some(Ok(v)),
+                            Timestamp::Offset(_) => None,
+                        })?),
+                    }
+                }
+                _ => args.add(list(values, |v| match v {
+                    Value::String(v) => Some(Ok(v)),
+                    _ => None,
+                })?),
+            },
+            value => bind_scalar::<Self>(args, value),
+        }
+    }
+}
+
+#[cfg(feature = "sqlx-postgres")]
+enum Timestamp {
+    Offset(DateTime<FixedOffset>),
+    Naive(NaiveDateTime),
+}
+
+// Parses a date in the canonical form of Value::Date
+#[cfg(feature = "sqlx-postgres")]
+fn date(value: &str) -> Result<NaiveDate, BoxDynError> {
+    Ok(NaiveDate::parse_from_str(value, "%Y-%m-%d")?)
+}
+
+// Parses a timestamp in the canonical form of Value::Timestamp, which only has an offset if it's
+// followed by `Z` or one after the time
+#[cfg(feature = "sqlx-postgres")]
+fn timestamp(value: &str) -> Result<Timestamp, BoxDynError> {
+    let offset = value.ends_with('Z')
+        || value
+            .get(19..)
+            .is_some_and(|rest| rest.contains(['+', '-']));
+
+    if offset {
+        Ok(Timestamp::Offset(DateTime::parse_from_rfc3339(value)?))
+    } else {
+        Ok(Timestamp::Naive(NaiveDateTime::parse_from_str(
+            value,
+            "%Y-%m-%dT%H:%M:%S%.f",
+        )?))
+    }
+}
+
+#[cfg(feature = "sqlx-postgres")]
+fn list<T, U>(
+    values: Vec<T>,
+    f: impl Fn(T) -> Option<Result<U, BoxDynError>>,
+) -> Result<Vec<U>, BoxDynError> {
+    values
+        .into_iter()
+        .map(|value| f(value).unwrap_or_else(|| Err("a list can't mix types".into())))
+        .collect()
+}
+
+#[cfg(all(test, feature = "sqlx-sqlite"))]
+mod test {
+    use sqlx::{Connection, Executor, SqliteConnection};
+
+    use crate::{
+        schema::{FieldSpec, FieldType, Schema},
+        sql::QueryBuilder,
+        UrlQuery,
+    };
+
+    #[tokio::test]
+    async fn test_build_sqlx_sqlite() {
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+
+        conn.execute(
+            "CREATE TABLE orders (id INTEGER PRIMARY KEY, status TEXT, price REAL, shipped BOOLEAN);
+             INSERT INTO orders VALUES
+                (1, 'pending', 10.5, false),
+                (2, 'shipped', 200.0, true),
+                (3, 'shipped', 50.0, true),
+                (4, 'cancelled', 300.0, false);",
+        )
+        .await
+        .unwrap();
+
+        let schema = Schema::new()
+            .field(FieldSpec::new("id", FieldType::Int).sortable(true))
+            .field(FieldSpec::new("status", FieldType::String))
+            .field(FieldSpec::new("price", FieldType::Float))
+            .field(FieldSpec::new("shipped", FieldType::Bool));
+
+        let query = "shipped=true&filter[]=price-ge-20&filter[]=status-in-shipped,cancelled\
+                     &sort=id-desc&limit=10";
+        let parsed = UrlQuery::with_schema(query, &schema).unwrap();
+
+        let mut builder = QueryBuilder::from_str("SELECT id, status FROM orders", parsed)
+            .schema(&schema)
+            .build_sqlx::<sqlx::Sqlite>()
+            .unwrap();
+
+        let rows: Vec<(i64, String)> = builder.build_query_as().fetch_all(&mut conn).await.unwrap();
+
+        assert_eq!(rows, [(3, "shipped".into()), (2, "shipped".into())]);
+    }
+
+    #[tokio::test]
+    async fn test_build_sqlx_sqlite_escaped_pattern() {
+        let mut conn = SqliteConnection::connect("sqlite::memory:").await.unwrap();
+
+        conn.execute(
+            "CREATE TABLE products (id INTEGER PRIMARY KEY, name TEXT);
+             INSERT INTO products VALUES
+                (1, '50% off'),
+                (2, '50 dollars off'),
+                (3, 'save_now'),
+                (4, 'save now'),
+                (5, '50\\% off');",
+        )
+        .await
+        .unwrap();
+
+        for (query, expected) in [
+            ("filter[]=name-ct-50%25", vec![1]),
+            ("filter[]=name-ct-e_n", vec![3]),
+            ("filter[]=name-sw-50%25", vec![1]),
+            ("filter[]=name-ew-\\%25%20off", vec![5]),
+        ] {
+            let parsed = UrlQuery::new(query, ["name"]).unwrap();
+
+            let mut builder = QueryBuilder::from_str("SELECT id FROM products", parsed)
+                .build_sqlx::<sqlx::Sqlite>()
+                .unwrap();
+
+            let ids: Vec<i64> = builder
+                .build_query_scalar()
+                .fetch_all(&mut conn)
+                .await
+                .unwrap();
+
+            assert_eq!(ids, expected, "{}", query);
+        }
+    }
+
+    #[test]
+    fn test_build_sqlx_shift_bind() {
+        let parsed = UrlQuery::new("status=paid", ["status"]).unwrap();
+
+        let result = QueryBuilder::from_str("SELECT * FROM orders WHERE user_id = ?1", parsed)
+            .shift_bind(1)
+            .build_sqlx::<sqlx::Sqlite>();
+
+        assert!(matches!(result, Err(sqlx::Error::InvalidArgument(_))));
+    }
+}
+
+#[cfg(all(test, feature = "sqlx-postgres"))]
+mod test_postgres {
+    use sqlx::{postgres::PgArguments, Connection, Executor, PgConnection};
+
+    use crate::{
+        bind::BindValue,
+        schema::{FieldSpec, FieldType, Schema},
+        sql::QueryBuilder,
+        value::Value,
+        UrlQuery,
+    };
+
+    fn bind(value: Value) -> Result<(), sqlx::error::BoxDynError> {
+        sqlx::Postgres::bind_value(&mut PgArguments::default(), value)
+    }
+
+    #[test]
+    fn test_bind_value_postgres() {
+        // Uuids, dates and timestamps are parsed to be bound as their types
+        assert!(bind(Value::Uuid("8bd8a6fb-e2b2-47ab-b3db-4f47c067ba5e".into())).is_ok());
+        assert!(bind(Value::Uuid("8bd8a6fb".into())).is_err());
+        assert!(bind(Value::Date("2024-02-29".into())).is_ok());
+        assert!(bind(Value::Date("2024-02-30".into())).is_err());
+        assert!(bind(Value::Timestamp("2024-01-31T12:00:00.5Z".into())).is_ok());
+        assert!(bind(Value::Timestamp("2024-01-31T12:00:00-05:00".into())).is_ok());
+        assert!(bind(Value::Timestamp("2024-01-31T12:00:00".into())).is_ok());
+        assert!(bind(Value::Timestamp("2024-01-31".into())).is_err());
+
+        assert!(bind(Value::List(vec![
+            Value::Uuid("8bd8a6fb-e2b2-47ab-b3db-4f47c067ba5e".into()),
+            Value::Uuid("00000000-0000-0000-0000-000000000000".into()),
+        ]))
+        .is_ok());
+        assert!(bind(Value::List(vec![
+            Value::Timestamp("2024-01-31T12:00:00Z".into()),
+            Value::Timestamp("2024-01-31T12:00:00".into()),
+        ]))
+        .is_err());
+    }
+
+    #[tokio::test]
+    #[ignore = "needs a Postgres database in DATABASE_URL"]
+    async fn test_build_sqlx_postgres() {
+        let url = std::env::var("DATABASE_URL").unwrap();
+        let mut conn = PgConnection::connect(&url).await.unwrap();
+
+        conn.execute(
+            "CREATE TEMPORARY TABLE orders (id BIGINT, user_id UUID, due DATE, created_at TIMESTAMPTZ);
+             INSERT INTO orders VALUES
+                (1, '8bd8a6fb-e2b2-47ab-b3db-4f47c067ba5e', '2024-01-31', '2024-01-31T12:00:00Z'),
+                (2, '8bd8a6fb-e2b2-47ab-b3db-4f47c067ba5e', '2024-02-29', '2024-02-29T12:00:00Z'),
+                (3, '00000000-0000-0000-0000-000000000000', '2024-02-29', '2024-02-29T12:00:00Z');",
+        )
+        .await
+        .unwrap();
+
+        let schema = Schema::new()
+            .field(FieldSpec::new("id", FieldType::Int).sortable(true))
+            .field(FieldSpec::new("userId", FieldType::Uuid))
+            .field(FieldSpec::new("due", FieldType::Date))
+            .field(FieldSpec::new("createdAt", FieldType::Timestamp));
+
+        let query = "filter[]=userId-in-8BD8A6FB-E2B2-47AB-B3DB-4F47C067BA5E,\
+                     11111111-1111-1111-1111-111111111111&due=2024-02-29\
+                     &filter[]=createdAt-ge-2024-02-01T00:00:00%2B01:00&sort=id-asc";
+        let parsed = UrlQuery::with_schema(query, &schema).unwrap();
+
+        let mut builder = QueryBuilder::from_str("SELECT id FROM orders", parsed)
+            .schema(&schema)
+            .convert_case(convert_case::Case::Snake)
+            .array_binds(true)
+            .build_sqlx::<sqlx::Postgres>()
+            .unwrap();
+
+        let ids: Vec<i64> = builder
+            .build_query_scalar()
+            .fetch_all(&mut conn)
+            .await
+            .unwrap();
+
+        assert_eq!(ids, [2]);
+    }
+}
@@ -0,0 +1,136 @@
+use crate::{
+    encoding::{decode, decode_base64, encode, encode_base64},
+    ErrorKind, ParseError,
+};
+
+/// Whether a [`Cursor`] selects the rows after or before it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Direction {
+    After,
+    Before,
+}
+
+impl Direction {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Direction::After => "after",
+            Direction::Before => "before",
+        }
+    }
+}
+
+// cursor=YWZ0ZXIsMjAyNC0wMS0zMSwxMg -> the rows after ("2024-01-31", "12")
+/// A position in the sorted rows for keyset pagination, given with the `cursor` param. It holds the
+/// values of the last (or first) row of a page for the sorted fields followed by the tiebreaker,
+/// see [`QueryBuilder::cursor_fields`]. The sorted fields have to be `NOT NULL`.
+///
+/// [`QueryBuilder::cursor_fields`]: crate::sql::QueryBuilder::cursor_fields
+///
+/// # Examples
+///
+/// ```
+/// use query::{cursor::{Cursor, Direction}, UrlQuery};
+///
+/// // The values of the last row of the page for `createdAt` and the tiebreaker `id`
+/// let cursor = Cursor::after(["2024-01-31", "12"]);
+///
+/// let parsed = UrlQuery::new(
+///     &format!("sort=createdAt-desc&cursor={}", cursor.encode()),
+///     ["createdAt"],
+/// )
+/// .unwrap();
+///
+/// assert_eq!(parsed.cursor, Some(cursor));
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cursor {
+    pub direction: Direction,
+    pub values: Vec<String>,
+}
+
+impl Cursor {
+    /// Returns a cursor for the rows after the row with these values.
+    pub fn after<T>(values: T) -> Self
+    where
+        T: IntoIterator,
+        T::Item: Into<String>,
+    {
+        Self {
+            direction: Direction::After,
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Returns a cursor for the rows before the row with these values.
+    pub fn before<T>(values: T) -> Self
+    where
+        T: IntoIterator,
+        T::Item: Into<String>,
+    {
+        Self {
+            direction: Direction::Before,
+            values: values.into_iter().map(Into::into).collect(),
+        }
+    }
+
+    /// Parses a cursor returned by [`encode`](Self::encode).
+    pub fn new(str: &str) -> Result<Self, ParseError> {
+        let decoded = decode_base64(str)
+            .and_then(|bytes| String::from_utf8(bytes).ok())
+            .ok_or(ErrorKind::InvalidCursor)?;
+
+        let mut parts = decoded.split(',');
+        let direction = match parts.next() {
+            Some("after") => Direction::After,
+            Some("before") => Direction::Before,
+            _ => Err(ErrorKind::InvalidCursor)?,
+        };
+
+        let values: Vec<String> = parts.map(decode).collect();
+        if values.is_empty() {
+            Err(ErrorKind::InvalidCursor)?
+        }
+
+        Ok(Self { direction, values })
+    }
+
+    /// Returns the cursor as an opaque string to use as the `cursor` param.
+    pub fn encode(&self) -> String {
+        let mut cursor = String::from(self.direction.as_str());
+        for value in &self.values {
+            cursor.push(',');
+            cursor.push_str(&encode(value));
+        }
+
+        encode_base64(cursor.as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::ErrorKind;
+
+    use super::{Cursor, Direction};
+
+    #[test]
+    fn test_encode() {
+        let cursor = Cursor::after(["2024-01-31T12:00:00Z", "a,b", ""]);
+        assert_eq!(Cursor::new(&cursor.encode()), Ok(cursor));
+
+        let cursor = Cursor::before(["12"]);
+        assert_eq!(cursor.encode(), "YmVmb3JlLDEy");
+        assert_eq!(
+            Cursor::new("YmVmb3JlLDEy").unwrap().direction,
+            Direction::Before
+        );
+    }
+
+    #[test]
+    fn test_new_invalid() {
+        // "after", "sideways,1" and an invalid base64 string
+        for cursor in ["YWZ0ZXI", "c2lkZXdheXMsMQ", "YWZ0ZXIsMQ=="] {
+            let err = Cursor::new(cursor).unwrap_err();
+            assert_eq!(err.kind, ErrorKind::InvalidCursor, "{}", cursor);
+        }
+    }
+}
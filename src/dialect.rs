@@ -0,0 +1,386 @@
+use convert_case::{Case, Casing};
+
+use crate::sort::{Nulls, SortBy};
+
+/// The SQL that differs between databases. Implement this to use [`QueryBuilder`] with a database
+/// that isn't covered by [`Database`]. The provided methods generate standard SQL.
+///
+/// [`QueryBuilder`]: crate::sql::QueryBuilder
+///
+/// # Examples
+///
+/// ```
+/// use query::{dialect::Dialect, sql::QueryBuilder, UrlQuery};
+///
+/// struct Oracle;
+///
+/// impl Dialect for Oracle {
+///     fn push_placeholder(&self, sql: &mut String, idx: usize) {
+///         sql.push(':');
+///         sql.push_str(&idx.to_string());
+///     }
+/// }
+///
+/// let parsed = UrlQuery::new("userId=1&limit=10", ["userId"]).unwrap();
+///
+/// let (sql, _) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+///     .set_dialect(Oracle)
+///     .build()
+///     .unwrap();
+///
+/// assert_eq!(
+///     sql,
+///     r#"SELECT * FROM orders WHERE "userId" = :1 OFFSET 0 ROWS FETCH NEXT 10 ROWS ONLY"#
+/// );
+/// ```
+pub trait Dialect: Send + Sync {
+    /// Pushes the placeholder for the bind parameter numbered `idx`, starting from 1.
+    fn push_placeholder(&self, sql: &mut String, idx: usize);
+
+    /// Returns the identifier quoted so it can't be mistaken for a keyword.
+    fn quote_identifier(&self, ident: &str) -> String {
+        quote(ident, '"', '"')
+    }
+
+    /// Pushes a pattern match of `column` against the bind parameter numbered `idx`. `escape` is
+    /// the character that escapes `%` and `_` in the bound pattern, if the pattern was escaped, and
+    /// is given with an `ESCAPE` clause.
+    fn push_like(
+        &self,
+        sql: &mut String,
+        column: &str,
+        idx: usize,
+        case_insensitive: bool,
+        escape: Option<char>,
+    ) {
+        if case_insensitive {
+            push_lower_like(self, sql, column, idx);
+        } else {
+            push_like(self, sql, column, idx);
+        }
+        push_escape(sql, escape);
+    }
+
+    /// Pushes the ORDER BY expression for `column`.
+    fn push_sort(&self, sql: &mut String, column: &str, sort_by: &SortBy, nulls: Option<&Nulls>) {
+        push_sort(sql, column, sort_by, nulls);
+    }
+
+    /// Pushes the pagination clause.
+    fn push_pagination(&self, sql: &mut String, limit: Option<u64>, offset: Option<u64>) {
+        push_offset_fetch(sql, limit, offset);
+    }
+
+    /// Returns a condition that is always `value`.
+    fn boolean_literal(&self, value: bool) -> &'static str {
+        if value {
+            "TRUE"
+        } else {
+            "FALSE"
+        }
+    }
+
+    /// Whether a list can be bound as a single array, eg `status = ANY($1)`.
+    fn supports_array_binds(&self) -> bool {
+        false
+    }
+
+    /// Whether columns can be compared as a row value, eg `(created_at, id) < ($1, $2)`. Without
+    /// row values, the comparison is expanded into `created_at < $1 OR (created_at = $2 AND id < $3)`.
+    fn supports_row_values(&self) -> bool {
+        false
+    }
+
+    /// Whether a query has to have an ORDER BY to be paginated.
+    fn requires_sort_to_paginate(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Database {
+    Postgres,
+    MySQL,
+    /// Uses `?NNN` placeholders.
+    Sqlite,
+    /// Uses `@pN` placeholders and `OFFSET n ROWS FETCH NEXT m ROWS ONLY` pagination.
+    MsSql,
+}
+
+impl Dialect for Database {
+    fn push_placeholder(&self, sql: &mut String, idx: usize) {
+        match self {
+            Database::Postgres => {
+                sql.push('$');
+                sql.push_str(&idx.to_string());
+            }
+            Database::MySQL => sql.push('?'),
+            Database::Sqlite => {
+                sql.push('?');
+                sql.push_str(&idx.to_string());
+            }
+            Database::MsSql => {
+                sql.push_str("@p");
+                sql.push_str(&idx.to_string());
+            }
+        }
+    }
+
+    fn quote_identifier(&self, ident: &str) -> String {
+        match self {
+            Database::MySQL => quote(ident, '`', '`'),
+            Database::MsSql => quote(ident, '[', ']'),
+            _ => quote(ident, '"', '"'),
+        }
+    }
+
+    fn push_like(
+        &self,
+        sql: &mut String,
+        column: &str,
+        idx: usize,
+        case_insensitive: bool,
+        escape: Option<char>,
+    ) {
+        // Postgres and MySQL escape patterns with a backslash by default
+        match self {
+            Database::Postgres if case_insensitive => {
+                sql.push_str(column);
+                sql.push_str(" ILIKE ");
+                self.push_placeholder(sql, idx);
+            }
+            // SQLite's LIKE is already case-insensitive, and it has no default escape character
+            Database::Sqlite => {
+                push_like(self, sql, column, idx);
+                push_escape(sql, escape);
+            }
+            // SQL Server has no default escape character, and `[` starts a character class so it's
+            // escaped too
+            Database::MsSql => {
+                if case_insensitive {
+                    sql.push_str("LOWER(");
+                    sql.push_str(column);
+                    sql.push_str(") LIKE LOWER(");
+                } else {
+                    sql.push_str(column);
+                    sql.push_str(" LIKE ");
+                }
+
+                match escape {
+                    Some(escape) => {
+                        sql.push_str("REPLACE(");
+                        self.push_placeholder(sql, idx);
+                        sql.push_str(", '[', '");
+                        sql.push(escape);
+                        sql.push_str("[')");
+                    }
+                    None => self.push_placeholder(sql, idx),
+                }
+
+                if case_insensitive {
+                    sql.push(')');
+                }
+                push_escape(sql, escape);
+            }
+            // MySQL has no ILIKE
+            _ if case_insensitive => push_lower_like(self, sql, column, idx),
+            _ => push_like(self, sql, column, idx),
+        }
+    }
+
+    fn push_sort(&self, sql: &mut String, column: &str, sort_by: &SortBy, nulls: Option<&Nulls>) {
+        match (self, nulls) {
+            // MySQL has no NULLS FIRST/LAST, ISNULL(column) is 1 for NULLs
+            (Database::MySQL, Some(nulls)) => {
+                sql.push_str("ISNULL(");
+                sql.push_str(column);
+                match nulls {
+                    Nulls::FIRST => sql.push_str(") DESC, "),
+                    Nulls::LAST => sql.push_str(") ASC, "),
+                }
+                push_sort(sql, column, sort_by, None);
+            }
+            // SQL Server has neither, and its ISNULL takes a replacement value
+            (Database::MsSql, Some(nulls)) => {
+                sql.push_str("CASE WHEN ");
+                sql.push_str(column);
+                match nulls {
+                    Nulls::FIRST => sql.push_str(" IS NULL THEN 0 ELSE 1 END ASC, "),
+                    Nulls::LAST => sql.push_str(" IS NULL THEN 1 ELSE 0 END ASC, "),
+                }
+                push_sort(sql, column, sort_by, None);
+            }
+            _ => push_sort(sql, column, sort_by, nulls),
+        }
+    }
+
+    fn push_pagination(&self, sql: &mut String, limit: Option<u64>, offset: Option<u64>) {
+        if let Database::MsSql = self {
+            push_offset_fetch(sql, limit, offset);
+            return;
+        }
+
+        // MySQL and SQLite don't allow an OFFSET without a LIMIT
+        let limit = match (limit, offset, self) {
+            (Some(limit), _, _) => Some(limit.to_string()),
+            (None, Some(_), Database::MySQL) => Some(u64::MAX.to_string()),
+            (None, Some(_), Database::Sqlite) => Some(String::from("-1")),
+            _ => None,
+        };
+
+        if let Some(limit) = limit {
+            sql.push_str(" LIMIT ");
+            sql.push_str(&limit);
+        }
+
+        if let Some(offset) = offset {
+            sql.push_str(" OFFSET ");
+            sql.push_str(&offset.to_string());
+        }
+    }
+
+    fn boolean_literal(&self, value: bool) -> &'static str {
+        match (self, value) {
+            // SQL Server has no boolean literals
+            (Database::MsSql, true) => "1 = 1",
+            (Database::MsSql, false) => "1 = 0",
+            (_, true) => "TRUE",
+            (_, false) => "FALSE",
+        }
+    }
+
+    fn supports_array_binds(&self) -> bool {
+        matches!(self, Database::Postgres)
+    }
+
+    fn supports_row_values(&self) -> bool {
+        // MySQL has row values, but optimizes the expanded comparison more reliably
+        matches!(self, Database::Postgres | Database::Sqlite)
+    }
+
+    fn requires_sort_to_paginate(&self) -> bool {
+        // OFFSET/FETCH is only allowed after an ORDER BY
+        matches!(self, Database::MsSql)
+    }
+}
+
+/// Returns the field as a quoted column, prefixed with its quoted table.
+pub(crate) fn column(
+    table: Option<&&str>,
+    field: &str,
+    case: Option<Case>,
+    dialect: &dyn Dialect,
+) -> String {
+    let mut column = String::new();
+    if let Some(table) = table {
+        column.push_str(&dialect.quote_identifier(table));
+        column.push('.');
+    }
+
+    // Check if we need to convert case
+    match case {
+        Some(case) => column.push_str(&dialect.quote_identifier(&field.to_case(case))),
+        None => column.push_str(&dialect.quote_identifier(field)),
+    }
+
+    column
+}
+
+fn quote(ident: &str, open: char, close: char) -> String {
+    let mut quoted = String::with_capacity(ident.len() + 2);
+    quoted.push(open);
+    for c in ident.chars() {
+        if c == close {
+            quoted.push(close);
+        }
+        quoted.push(c);
+    }
+    quoted.push(close);
+
+    quoted
+}
+
+fn push_like<D: Dialect + ?Sized>(dialect: &D, sql: &mut String, column: &str, idx: usize) {
+    sql.push_str(column);
+    sql.push_str(" LIKE ");
+    dialect.push_placeholder(sql, idx);
+}
+
+fn push_lower_like<D: Dialect + ?Sized>(dialect: &D, sql: &mut String, column: &str, idx: usize) {
+    sql.push_str("LOWER(");
+    sql.push_str(column);
+    sql.push_str(") LIKE LOWER(");
+    dialect.push_placeholder(sql, idx);
+    sql.push(')');
+}
+
+// Pushes ` ESCAPE '\'`
+fn push_escape(sql: &mut String, escape: Option<char>) {
+    if let Some(escape) = escape {
+        sql.push_str(" ESCAPE '");
+        if escape == '\'' {
+            sql.push('\'');
+        }
+        sql.push(escape);
+        sql.push('\'');
+    }
+}
+
+fn push_sort(sql: &mut String, column: &str, sort_by: &SortBy, nulls: Option<&Nulls>) {
+    sql.push_str(column);
+    sql.push(' ');
+    sql.push_str(sort_by.as_str());
+    if let Some(nulls) = nulls {
+        sql.push(' ');
+        sql.push_str(nulls.as_str());
+    }
+}
+
+fn push_offset_fetch(sql: &mut String, limit: Option<u64>, offset: Option<u64>) {
+    if limit.is_none() && offset.is_none() {
+        return;
+    }
+
+    sql.push_str(" OFFSET ");
+    sql.push_str(&offset.unwrap_or(0).to_string());
+    sql.push_str(" ROWS");
+
+    if let Some(limit) = limit {
+        sql.push_str(" FETCH NEXT ");
+        sql.push_str(&limit.to_string());
+        sql.push_str(" ROWS ONLY");
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{Database, Dialect};
+
+    struct Oracle;
+
+    impl Dialect for Oracle {
+        fn push_placeholder(&self, sql: &mut String, idx: usize) {
+            sql.push(':');
+            sql.push_str(&idx.to_string());
+        }
+    }
+
+    #[test]
+    fn test_quote_identifier() {
+        assert_eq!(Database::Postgres.quote_identifier("order"), "\"order\"");
+        assert_eq!(Database::Sqlite.quote_identifier("a\"b"), "\"a\"\"b\"");
+        assert_eq!(Database::MySQL.quote_identifier("group"), "`group`");
+        assert_eq!(Database::MsSql.quote_identifier("a]b"), "[a]]b]");
+    }
+
+    #[test]
+    fn test_push_like_escape() {
+        let mut sql = String::new();
+        Oracle.push_like(&mut sql, "name", 1, false, Some('\\'));
+        assert_eq!(sql, "name LIKE :1 ESCAPE '\\'");
+
+        let mut sql = String::new();
+        Oracle.push_like(&mut sql, "name", 1, true, None);
+        assert_eq!(sql, "LOWER(name) LIKE LOWER(:1)");
+    }
+}
@@ -0,0 +1,153 @@
+/// Decodes an `application/x-www-form-urlencoded` string. `+` is decoded as a space and invalid
+/// percent escapes are kept as they are.
+pub(crate) fn decode(str: &str) -> String {
+    let bytes = str.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'+' => decoded.push(b' '),
+            b'%' => match (bytes.get(i + 1), bytes.get(i + 2)) {
+                (Some(&hi), Some(&lo)) if hi.is_ascii_hexdigit() && lo.is_ascii_hexdigit() => {
+                    decoded.push(hex_value(hi) << 4 | hex_value(lo));
+                    i += 2;
+                }
+                _ => decoded.push(b'%'),
+            },
+            b => decoded.push(b),
+        }
+
+        i += 1;
+    }
+
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+/// Percent-encodes a string so that [`decode`] returns it unchanged. Only unreserved characters are
+/// kept, so the result never contains a delimiter such as `&`, `,` or `;`.
+pub(crate) fn encode(str: &str) -> String {
+    encode_except(str, b"-._~")
+}
+
+/// Percent-encodes a string like [`encode`], and also encodes `-` for the parts of a param that are
+/// split on it, like the field of a filter or a sort.
+pub(crate) fn encode_field(str: &str) -> String {
+    encode_except(str, b"._~")
+}
+
+fn encode_except(str: &str, unreserved: &[u8]) -> String {
+    const HEX: &[u8; 16] = b"0123456789ABCDEF";
+
+    let mut encoded = String::with_capacity(str.len());
+    for &b in str.as_bytes() {
+        if b.is_ascii_alphanumeric() || unreserved.contains(&b) {
+            encoded.push(b as char);
+        } else {
+            encoded.push('%');
+            encoded.push(HEX[(b >> 4) as usize] as char);
+            encoded.push(HEX[(b & 0xf) as usize] as char);
+        }
+    }
+
+    encoded
+}
+
+const BASE64: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// Encodes bytes as unpadded base64url, RFC 4648 section 5, which is safe to use in a url query.
+pub(crate) fn encode_base64(bytes: &[u8]) -> String {
+    let mut encoded = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let mut n = 0u32;
+        for (i, &b) in chunk.iter().enumerate() {
+            n |= (b as u32) << (16 - 8 * i);
+        }
+
+        // 1, 2 or 3 bytes take 2, 3 or 4 characters
+        for i in 0..=chunk.len() {
+            encoded.push(BASE64[(n >> (18 - 6 * i) & 0x3f) as usize] as char);
+        }
+    }
+
+    encoded
+}
+
+/// Decodes unpadded base64url, returning `None` if it isn't valid.
+pub(crate) fn decode_base64(str: &str) -> Option<Vec<u8>> {
+    let mut decoded = Vec::with_capacity(str.len() / 4 * 3 + 2);
+    for chunk in str.as_bytes().chunks(4) {
+        if chunk.len() == 1 {
+            return None;
+        }
+
+        let mut n = 0u32;
+        for (i, &c) in chunk.iter().enumerate() {
+            let value = BASE64.iter().position(|&b| b == c)? as u32;
+            n |= value << (18 - 6 * i);
+        }
+
+        for i in 0..chunk.len() - 1 {
+            decoded.push((n >> (16 - 8 * i)) as u8);
+        }
+    }
+
+    Some(decoded)
+}
+
+fn hex_value(b: u8) -> u8 {
+    match b {
+        b'0'..=b'9' => b - b'0',
+        b'a'..=b'f' => b - b'a' + 10,
+        _ => b - b'A' + 10,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode, decode_base64, encode, encode_base64, encode_field};
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(decode("bob%20smith"), "bob smith");
+        assert_eq!(decode("bob+smith"), "bob smith");
+        assert_eq!(decode("bob%40example.com"), "bob@example.com");
+        assert_eq!(decode("filter%5B%5D"), "filter[]");
+        assert_eq!(decode("caf%C3%A9"), "café");
+        assert_eq!(decode("100%"), "100%");
+        assert_eq!(decode("%zz"), "%zz");
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode("bob smith"), "bob%20smith");
+        assert_eq!(encode("2024-01-31"), "2024-01-31");
+        assert_eq!(encode("a+b&c=d,e;f(g)"), "a%2Bb%26c%3Dd%2Ce%3Bf%28g%29");
+        assert_eq!(encode("café"), "caf%C3%A9");
+        assert_eq!(encode_field("created-at"), "created%2Dat");
+
+        for str in ["100%", "bob+smith", "filter[]", "caf\u{e9} au lait"] {
+            assert_eq!(decode(&encode(str)), str);
+        }
+    }
+
+    #[test]
+    fn test_base64() {
+        for (str, encoded) in [
+            ("", ""),
+            ("f", "Zg"),
+            ("fo", "Zm8"),
+            ("foo", "Zm9v"),
+            ("foob", "Zm9vYg"),
+            ("fooba", "Zm9vYmE"),
+            ("foobar", "Zm9vYmFy"),
+            ("\u{fb}\u{ff}", "w7vDvw"),
+        ] {
+            assert_eq!(encode_base64(str.as_bytes()), encoded);
+            assert_eq!(decode_base64(encoded).as_deref(), Some(str.as_bytes()));
+        }
+
+        assert_eq!(decode_base64("Zm9vY"), None);
+        assert_eq!(decode_base64("Zm9v+g"), None);
+    }
+}
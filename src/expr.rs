@@ -0,0 +1,330 @@
+use std::collections::HashMap;
+
+use convert_case::Case;
+
+use crate::{
+    dialect::{column, Dialect},
+    filter::Filter,
+    schema::Schema,
+    value::Value,
+    ErrorKind, ParseError,
+};
+
+// q=or(status-eq-a;and(status-eq-b;or(price-gt-10;featured-eq-true)))
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterExpr {
+    And(Vec<FilterExpr>),
+    Or(Vec<FilterExpr>),
+    Not(Box<FilterExpr>),
+    Filter(Filter),
+}
+
+impl FilterExpr {
+    /// Parses a filter expression. Groups are written as `and(...)`, `or(...)` or `not(...)` with
+    /// their expressions separated by `;`, and every other expression is parsed with
+    /// [`Filter::new`]. Use `%28`, `%29` and `%3B` for a `(`, `)` or `;` inside a filter. Groups
+    /// can be nested 32 deep.
+    pub fn new(str: &str) -> Result<Self, ParseError> {
+        let (expr, rest) = parse_expr(str, 0)?;
+        if !rest.is_empty() {
+            Err(ErrorKind::InvalidFilter)?
+        }
+
+        Ok(expr)
+    }
+
+    /// Returns an equivalent expression in a canonical form. Nested groups of the same kind are
+    /// flattened, the expressions of a group are sorted and deduplicated, a group of one expression
+    /// is replaced by that expression and a double `not` is removed. Filters are normalized with
+    /// [`Filter::normalized`].
+    pub fn normalized(&self) -> Self {
+        let exprs = match self {
+            FilterExpr::Filter(filter) => return FilterExpr::Filter(filter.normalized()),
+            FilterExpr::Not(expr) => {
+                return match expr.normalized() {
+                    FilterExpr::Not(expr) => *expr,
+                    expr => FilterExpr::Not(Box::new(expr)),
+                };
+            }
+            FilterExpr::And(exprs) | FilterExpr::Or(exprs) => exprs,
+        };
+
+        let and = matches!(self, FilterExpr::And(_));
+
+        let mut flattened = Vec::new();
+        for expr in exprs {
+            match (expr.normalized(), and) {
+                (FilterExpr::And(exprs), true) | (FilterExpr::Or(exprs), false) => {
+                    flattened.extend(exprs)
+                }
+                (expr, _) => flattened.push(expr),
+            }
+        }
+        flattened.sort_by_cached_key(|e| e.to_query());
+        flattened.dedup();
+
+        match flattened.len() {
+            1 => flattened.remove(0),
+            _ if and => FilterExpr::And(flattened),
+            _ => FilterExpr::Or(flattened),
+        }
+    }
+
+    /// Returns the expression as it's written in a url query, the inverse of [`FilterExpr::new`].
+    pub(crate) fn to_query(&self) -> String {
+        let (group, exprs) = match self {
+            FilterExpr::And(exprs) => ("and", exprs.iter().collect()),
+            FilterExpr::Or(exprs) => ("or", exprs.iter().collect()),
+            FilterExpr::Not(expr) => ("not", vec![expr.as_ref()]),
+            FilterExpr::Filter(filter) => return filter.to_query(),
+        };
+
+        let exprs: Vec<String> = exprs.into_iter().map(|e| e.to_query()).collect();
+
+        format!("{}({})", group, exprs.join(";"))
+    }
+
+    /// Returns every filter in the expression, in the order they appear.
+    pub fn filters(&self) -> Vec<&Filter> {
+        let mut filters = Vec::new();
+        self.collect_filters(&mut filters);
+
+        filters
+    }
+
+    fn collect_filters<'a>(&'a self, filters: &mut Vec<&'a Filter>) {
+        match self {
+            FilterExpr::And(exprs) | FilterExpr::Or(exprs) => {
+                exprs.iter().for_each(|e| e.collect_filters(filters))
+            }
+            FilterExpr::Not(expr) => expr.collect_filters(filters),
+            FilterExpr::Filter(filter) => filters.push(filter),
+        }
+    }
+
+    /// Returns the values to bind for this expression, in placeholder order. Each value is parsed
+    /// as the type of its field in the schema.
+    pub fn values(&self, schema: Option<&Schema>, array_binds: bool) -> Vec<Value> {
+        self.filters()
+            .into_iter()
+            .flat_map(|f| {
+                let field_type = schema
+                    .and_then(|schema| schema.get(&f.field))
+                    .map(|spec| &spec.field_type);
+                f.values(field_type, array_binds)
+            })
+            .collect()
+    }
+
+    /// Returns the SQL for this expression, numbering placeholders from `idx`. Groups with more
+    /// than one expression are parenthesised.
+    pub fn to_sql_map_table(
+        &self,
+        idx: usize,
+        map_columns: &HashMap<&str, &str>,
+        case: Option<Case>,
+        dialect: &dyn Dialect,
+        array_binds: bool,
+    ) -> String {
+        let column = |field: &str| column(map_columns.get(field), field, case, dialect);
+
+        self.to_sql_columns(idx, &column, dialect, array_binds)
+    }
+
+    /// Returns the SQL for this expression with the columns rendered by `column`.
+    pub(crate) fn to_sql_columns(
+        &self,
+        idx: usize,
+        column: &dyn Fn(&str) -> String,
+        dialect: &dyn Dialect,
+        array_binds: bool,
+    ) -> String {
+        let mut sql = String::new();
+        let mut idx = idx;
+        self.to_sql(&mut sql, &mut idx, column, dialect, array_binds);
+
+        sql
+    }
+
+    fn to_sql(
+        &self,
+        sql: &mut String,
+        idx: &mut usize,
+        column: &dyn Fn(&str) -> String,
+        dialect: &dyn Dialect,
+        array_binds: bool,
+    ) {
+        match self {
+            FilterExpr::And(exprs) | FilterExpr::Or(exprs) => {
+                let separator = match self {
+                    FilterExpr::And(_) => " AND ",
+                    _ => " OR ",
+                };
+
+                // An empty AND is always true and an empty OR is always false
+                if exprs.is_empty() {
+                    sql.push_str(dialect.boolean_literal(separator == " AND "));
+                }
+
+                if exprs.len() > 1 {
+                    sql.push('(');
+                }
+                for (i, expr) in exprs.iter().enumerate() {
+                    if i > 0 {
+                        sql.push_str(separator);
+                    }
+                    expr.to_sql(sql, idx, column, dialect, array_binds);
+                }
+                if exprs.len() > 1 {
+                    sql.push(')');
+                }
+            }
+            FilterExpr::Not(expr) => {
+                sql.push_str("NOT (");
+                expr.to_sql(sql, idx, column, dialect, array_binds);
+                sql.push(')');
+            }
+            FilterExpr::Filter(filter) => {
+                let column = column(&filter.field);
+                sql.push_str(&filter.to_sql(column, *idx, dialect, array_binds));
+                *idx += filter.values(None, array_binds).len();
+            }
+        }
+    }
+}
+
+// The deepest groups can be nested, so that an expression can't overflow the stack
+const MAX_DEPTH: usize = 32;
+
+// Parses an expression from the start of str and returns it with the unparsed remainder. `depth`
+// is the number of groups the expression is in.
+fn parse_expr(str: &str, depth: usize) -> Result<(FilterExpr, &str), ParseError> {
+    for group in ["and(", "or(", "not("] {
+        let rest = match str.strip_prefix(group) {
+            Some(rest) => rest,
+            None => continue,
+        };
+
+        if depth == MAX_DEPTH {
+            Err(ErrorKind::InvalidFilter)?
+        }
+
+        let mut exprs = Vec::new();
+        let mut rest = rest;
+        loop {
+            let (expr, r) = parse_expr(rest, depth + 1)?;
+            exprs.push(expr);
+
+            match r.as_bytes().first() {
+                Some(b';') => rest = &r[1..],
+                Some(b')') => {
+                    rest = &r[1..];
+                    break;
+                }
+                _ => Err(ErrorKind::InvalidFilter)?,
+            }
+        }
+
+        let expr = match group {
+            "and(" => FilterExpr::And(exprs),
+            "or(" => FilterExpr::Or(exprs),
+            _ if exprs.len() == 1 => FilterExpr::Not(Box::new(exprs.remove(0))),
+            _ => Err(ErrorKind::InvalidFilter)?,
+        };
+
+        return Ok((expr, rest));
+    }
+
+    let end = str.find([';', ')']).unwrap_or(str.len());
+    let filter = Filter::new(&str[..end])?;
+
+    Ok((FilterExpr::Filter(filter), &str[end..]))
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use crate::{filter::Filter, sql::Database, ErrorKind};
+
+    use super::FilterExpr;
+
+    #[test]
+    fn test_new() {
+        let expr = FilterExpr::new("or(status-eq-a;not(price-gt-10))").unwrap();
+
+        let expected = FilterExpr::Or(vec![
+            FilterExpr::Filter(Filter::new("status-eq-a").unwrap()),
+            FilterExpr::Not(Box::new(FilterExpr::Filter(
+                Filter::new("price-gt-10").unwrap(),
+            ))),
+        ]);
+
+        assert_eq!(expr, expected);
+    }
+
+    #[test]
+    fn test_new_invalid() {
+        for invalid in [
+            "or(status-eq-a",
+            "or(status-eq-a))",
+            "or()",
+            "not(status-eq-a;status-eq-b)",
+            "status-eq-a;status-eq-b",
+        ] {
+            assert_eq!(
+                FilterExpr::new(invalid).map_err(|e| e.kind),
+                Err(ErrorKind::InvalidFilter)
+            );
+        }
+    }
+
+    #[test]
+    fn test_new_max_depth() {
+        let nested =
+            |depth: usize| format!("{}status-eq-a{}", "not(".repeat(depth), ")".repeat(depth));
+
+        assert!(FilterExpr::new(&nested(32)).is_ok());
+        assert_eq!(
+            FilterExpr::new(&nested(33)).map_err(|e| e.kind),
+            Err(ErrorKind::InvalidFilter)
+        );
+        assert_eq!(
+            FilterExpr::new(&nested(10_000)).map_err(|e| e.kind),
+            Err(ErrorKind::InvalidFilter)
+        );
+    }
+
+    #[test]
+    fn test_to_sql() {
+        let expr = FilterExpr::new(
+            "or(status-eq-a;and(status-eq-b;or(price-gt-10;featured-eq-true));id-in-1,2)",
+        )
+        .unwrap();
+
+        let sql = expr.to_sql_map_table(
+            2,
+            &HashMap::from([("id", "orders")]),
+            None,
+            &Database::Postgres,
+            false,
+        );
+
+        assert_eq!(
+            sql,
+            "(\"status\" = $2 OR (\"status\" = $3 AND (\"price\" > $4 OR \"featured\" = $5)) OR \"orders\".\"id\" IN ($6, $7))"
+        );
+        assert_eq!(expr.values(None, false).len(), 6);
+    }
+
+    #[test]
+    fn test_to_sql_empty_groups() {
+        let expr = FilterExpr::Or(vec![FilterExpr::And(vec![]), FilterExpr::Or(vec![])]);
+
+        let sql = expr.to_sql_map_table(1, &HashMap::new(), None, &Database::Postgres, false);
+        assert_eq!(sql, "(TRUE OR FALSE)");
+
+        let sql = expr.to_sql_map_table(1, &HashMap::new(), None, &Database::MsSql, false);
+        assert_eq!(sql, "(1 = 1 OR 1 = 0)");
+    }
+}
@@ -0,0 +1,36 @@
+//! Extractors that parse the query string of a request into a [`UrlQuery`](crate::UrlQuery),
+//! checked against the [`Queryable`](crate::schema::Queryable) type they're parameterised by.
+
+#[cfg(feature = "actix")]
+pub mod actix;
+#[cfg(feature = "axum")]
+pub mod axum;
+
+// The fixture the tests of both extractors share
+#[cfg(test)]
+mod test {
+    use crate::{
+        schema::{FieldSpec, FieldType, Queryable, Schema},
+        sql::QueryBuilder,
+        UrlQuery,
+    };
+
+    pub(super) struct Order;
+
+    impl Queryable for Order {
+        fn schema() -> Schema {
+            Schema::new()
+                .field(FieldSpec::new("userId", FieldType::Int))
+                .field(FieldSpec::new("price", FieldType::Float).sortable(true))
+        }
+    }
+
+    // The SQL the handlers of the tests respond with
+    pub(super) fn select_orders(query: UrlQuery) -> String {
+        let (sql, _) = QueryBuilder::from_str("SELECT * FROM orders", query)
+            .build()
+            .unwrap();
+
+        sql
+    }
+}
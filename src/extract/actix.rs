@@ -0,0 +1,244 @@
+use std::{marker::PhantomData, sync::Arc};
+
+use ::actix_web::{
+    dev::Payload, http::StatusCode, Error, FromRequest, HttpRequest, HttpResponse, ResponseError,
+};
+
+use crate::{schema::Queryable, ParseError, ParseErrors, UrlQuery};
+
+type ErrorHandler = Arc<dyn Fn(ParseErrors, &HttpRequest) -> Error + Send + Sync>;
+
+/// Parses the query string with the schema of `T`, failing with a `400 Bad Request` that describes
+/// every error unless a [`QueryConfig`] sets another error handler.
+///
+/// # Examples
+///
+/// ```ignore
+/// async fn get_orders(Query(query, _): Query<Order>) -> actix_web::Result<Json<Vec<Order>>> {
+///     ...
+/// }
+///
+/// App::new()
+///     .app_data(QueryConfig::default().require_limit(true))
+///     .route("/orders", web::get().to(get_orders))
+/// ```
+pub struct Query<T>(pub UrlQuery, pub PhantomData<fn() -> T>);
+
+impl<T> Query<T> {
+    pub fn into_inner(self) -> UrlQuery {
+        self.0
+    }
+}
+
+/// Configures the [`Query`] extractor, add it with `App::app_data`.
+#[derive(Clone, Default)]
+pub struct QueryConfig {
+    required: Vec<String>,
+    require_limit: bool,
+    require_offset: bool,
+    error_handler: Option<ErrorHandler>,
+}
+
+impl QueryConfig {
+    /// Requires the query to include the params, see [`UrlQuery::check_required`].
+    pub fn required<'a>(mut self, required: impl IntoIterator<Item = &'a str>) -> Self {
+        self.required = required.into_iter().map(Into::into).collect();
+
+        self
+    }
+
+    /// Requires the query to include a limit, see [`UrlQuery::check_limit`].
+    pub fn require_limit(mut self, require_limit: bool) -> Self {
+        self.require_limit = require_limit;
+
+        self
+    }
+
+    /// Requires the query to include an offset, see [`UrlQuery::check_offset`].
+    pub fn require_offset(mut self, require_offset: bool) -> Self {
+        self.require_offset = require_offset;
+
+        self
+    }
+
+    /// Sets the error returned when the query can't be parsed or is missing a required param.
+    pub fn error_handler<F>(mut self, f: F) -> Self
+    where
+        F: Fn(ParseErrors, &HttpRequest) -> Error + Send + Sync + 'static,
+    {
+        self.error_handler = Some(Arc::new(f));
+
+        self
+    }
+
+    // The checks run even if a param is invalid so that every error is returned, but a param is
+    // only reported once
+    fn parse(&self, query: &str, schema: &crate::schema::Schema) -> Result<UrlQuery, ParseErrors> {
+        let (parsed, mut errors) = UrlQuery::parse_partial(query, schema);
+
+        let reported = |errors: &[ParseError], key: &str| {
+            errors
+                .iter()
+                .any(|err| err.key.as_deref() == Some(key) || err.field.as_deref() == Some(key))
+        };
+
+        for r in self.required.iter() {
+            if !reported(&errors, r) {
+                if let Err(err) = parsed.check_required([r.as_str()]) {
+                    errors.push(err);
+                }
+            }
+        }
+        if self.require_limit && !reported(&errors, "limit") {
+            if let Err(err) = parsed.check_limit() {
+                errors.push(err);
+            }
+        }
+        if self.require_offset && !reported(&errors, "offset") {
+            if let Err(err) = parsed.check_offset() {
+                errors.push(err);
+            }
+        }
+
+        if !errors.is_empty() {
+            Err(ParseErrors { errors })?
+        }
+
+        Ok(parsed)
+    }
+}
+
+impl<T: Queryable> FromRequest for Query<T> {
+    type Error = Error;
+    type Future = std::future::Ready<Result<Self, Error>>;
+
+    fn from_request(req: &HttpRequest, _payload: &mut Payload) -> Self::Future {
+        let default = QueryConfig::default();
+        let config = req.app_data::<QueryConfig>().unwrap_or(&default);
+
+        let result = match config.parse(req.query_string(), &T::schema()) {
+            Ok(parsed) => Ok(Query(parsed, PhantomData)),
+            Err(errors) => match config.error_handler {
+                Some(ref handler) => Err(handler(errors, req)),
+                None => Err(QueryRejection(errors).into()),
+            },
+        };
+
+        std::future::ready(result)
+    }
+}
+
+/// The default error of the [`Query`] extractor, an RFC 7807 problem details body, see
+/// [`ParseErrors::to_problem_json`].
+#[derive(Debug)]
+pub struct QueryRejection(pub ParseErrors);
+
+impl std::fmt::Display for QueryRejection {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl ResponseError for QueryRejection {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::BAD_REQUEST
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code())
+            .content_type("application/problem+json")
+            .body(self.0.to_problem_json())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use actix_web::{
+        error::ErrorUnprocessableEntity,
+        http::{header, StatusCode},
+        test, web, App,
+    };
+
+    use crate::extract::test::{select_orders, Order};
+
+    use super::{Query, QueryConfig};
+
+    async fn get_orders(Query(query, _): Query<Order>) -> String {
+        select_orders(query)
+    }
+
+    #[actix_web::test]
+    async fn test_query_extractor() {
+        let app = test::init_service(
+            App::new()
+                .app_data(
+                    QueryConfig::default()
+                        .required(["userId"])
+                        .require_limit(true),
+                )
+                .route("/orders", web::get().to(get_orders)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/orders?userId=1&limit=10")
+            .to_request();
+        let body = test::call_and_read_body(&app, req).await;
+        assert_eq!(
+            body,
+            r#"SELECT * FROM orders WHERE "userId" = $1 LIMIT 10"#.as_bytes()
+        );
+
+        let req = test::TestRequest::get()
+            .uri("/orders?sort=price-asc")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/problem+json"
+        );
+
+        let body = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+        assert!(body.contains(r#""detail":"userId is required; limit is required""#));
+
+        // The config is checked even if a param is invalid, without reporting it again
+        let req = test::TestRequest::get()
+            .uri("/orders?userId=bob&sort=price-up")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let body = String::from_utf8(test::read_body(res).await.to_vec()).unwrap();
+        assert!(body.contains(
+            "\"detail\":\"invalid value userId in userId=bob at position 0, expected one of: int; \
+             invalid sort by in sort=price-up at position 11, expected one of: asc, desc; \
+             limit is required\""
+        ));
+    }
+
+    #[actix_web::test]
+    async fn test_query_extractor_error_handler() {
+        let config = QueryConfig::default()
+            .error_handler(|errors, _| ErrorUnprocessableEntity(errors.to_string()));
+
+        let app = test::init_service(
+            App::new()
+                .app_data(config)
+                .route("/orders", web::get().to(get_orders)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/orders?userId=bob")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let body = test::read_body(res).await;
+        assert_eq!(
+            body,
+            "invalid value userId in userId=bob at position 0, expected one of: int".as_bytes()
+        );
+    }
+}
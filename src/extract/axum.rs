@@ -0,0 +1,127 @@
+use std::marker::PhantomData;
+
+use ::axum::{
+    extract::FromRequestParts,
+    http::{header, request::Parts, StatusCode},
+    response::{IntoResponse, Response},
+};
+
+use crate::{schema::Queryable, ParseErrors, UrlQuery};
+
+/// Parses the query string with the schema of `T`, rejecting the request with a `400 Bad Request`
+/// that describes every error.
+///
+/// # Examples
+///
+/// ```ignore
+/// async fn get_orders(Query(query, _): Query<Order>) -> Json<Vec<Order>> {
+///     let orders = QueryBuilder::from_str("SELECT * FROM orders", query)
+///         .schema(&Order::schema())
+///         .build_sqlx::<Postgres>()
+///     ...
+/// }
+/// ```
+pub struct Query<T>(pub UrlQuery, pub PhantomData<fn() -> T>);
+
+impl<T> Query<T> {
+    pub fn into_inner(self) -> UrlQuery {
+        self.0
+    }
+}
+
+impl<T, S> FromRequestParts<S> for Query<T>
+where
+    T: Queryable,
+    S: Send + Sync,
+{
+    type Rejection = QueryRejection;
+
+    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
+        let query = parts.uri.query().unwrap_or("");
+
+        let parsed = UrlQuery::parse_all(query, &T::schema()).map_err(QueryRejection)?;
+
+        Ok(Query(parsed, PhantomData))
+    }
+}
+
+/// Rejects a request with an RFC 7807 problem details body, see
+/// [`ParseErrors::to_problem_json`].
+#[derive(Debug)]
+pub struct QueryRejection(pub ParseErrors);
+
+impl IntoResponse for QueryRejection {
+    fn into_response(self) -> Response {
+        (
+            StatusCode::BAD_REQUEST,
+            [(header::CONTENT_TYPE, "application/problem+json")],
+            self.0.to_problem_json(),
+        )
+            .into_response()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use axum::{
+        body::{to_bytes, Body},
+        http::{header, Request, StatusCode},
+        routing::get,
+        Router,
+    };
+    use tower::ServiceExt;
+
+    use crate::extract::test::{select_orders, Order};
+
+    use super::Query;
+
+    async fn get_orders(Query(query, _): Query<Order>) -> String {
+        select_orders(query)
+    }
+
+    async fn send(uri: &str) -> (StatusCode, Option<String>, String) {
+        let app = Router::new().route("/orders", get(get_orders));
+
+        let request = Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = app.oneshot(request).await.unwrap();
+
+        let status = response.status();
+        let content_type = response
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .map(|v| v.to_str().unwrap().to_owned());
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+
+        (
+            status,
+            content_type,
+            String::from_utf8(body.to_vec()).unwrap(),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_query_extractor() {
+        let (status, _, body) = send("/orders?userId=1&sort=price-desc").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(
+            body,
+            r#"SELECT * FROM orders WHERE "userId" = $1 ORDER BY "price" DESC"#
+        );
+
+        let (status, _, body) = send("/orders").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, "SELECT * FROM orders");
+    }
+
+    #[tokio::test]
+    async fn test_query_extractor_rejection() {
+        let (status, content_type, body) = send("/orders?userId=bob&sort=userId-asc").await;
+
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(content_type.as_deref(), Some("application/problem+json"));
+        assert!(body
+            .starts_with(r#"{"type":"about:blank","title":"Invalid query string","status":400,"#));
+        assert!(body.contains(r#""code":"invalid_value""#));
+        assert!(body.contains(r#""code":"field_not_sortable""#));
+    }
+}
@@ -1,10 +1,16 @@
 use std::str::FromStr;
 
-use convert_case::{Case, Casing};
+use convert_case::Case;
 
-use crate::{sql::Database, ParseError};
+use crate::{
+    dialect::{column, Dialect},
+    encoding::{decode, encode, encode_field},
+    schema::FieldType,
+    value::Value,
+    ErrorKind, ParseError,
+};
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Condition {
     EQ,
     NE,
@@ -12,6 +18,16 @@ pub enum Condition {
     GE,
     LT,
     LE,
+    IN,
+    NIN,
+    LIKE,
+    ILIKE,
+    SW,
+    EW,
+    CT,
+    NULL,
+    NOTNULL,
+    BETWEEN,
 }
 
 impl FromStr for Condition {
@@ -25,12 +41,49 @@ impl FromStr for Condition {
             "ge" => Ok(Condition::GE),
             "lt" => Ok(Condition::LT),
             "le" => Ok(Condition::LE),
-            _ => Err(ParseError::InvalidCondition),
+            "in" => Ok(Condition::IN),
+            "nin" => Ok(Condition::NIN),
+            "like" => Ok(Condition::LIKE),
+            "ilike" => Ok(Condition::ILIKE),
+            "sw" => Ok(Condition::SW),
+            "ew" => Ok(Condition::EW),
+            "ct" => Ok(Condition::CT),
+            "null" => Ok(Condition::NULL),
+            "notnull" => Ok(Condition::NOTNULL),
+            "between" => Ok(Condition::BETWEEN),
+            _ => Err(ParseError::new(ErrorKind::InvalidCondition).expected(CONDITIONS)),
         }
     }
 }
 
+const CONDITIONS: [&str; 16] = [
+    "eq", "ne", "gt", "ge", "lt", "le", "in", "nin", "like", "ilike", "sw", "ew", "ct", "null",
+    "notnull", "between",
+];
+
 impl Condition {
+    /// Returns the condition as it's written in a url query, eg `ge`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Condition::EQ => "eq",
+            Condition::NE => "ne",
+            Condition::GT => "gt",
+            Condition::GE => "ge",
+            Condition::LT => "lt",
+            Condition::LE => "le",
+            Condition::IN => "in",
+            Condition::NIN => "nin",
+            Condition::LIKE => "like",
+            Condition::ILIKE => "ilike",
+            Condition::SW => "sw",
+            Condition::EW => "ew",
+            Condition::CT => "ct",
+            Condition::NULL => "null",
+            Condition::NOTNULL => "notnull",
+            Condition::BETWEEN => "between",
+        }
+    }
+
     pub fn as_str(&self) -> &str {
         match self {
             Condition::EQ => "=",
@@ -39,37 +92,189 @@ impl Condition {
             Condition::GE => ">=",
             Condition::LT => "<",
             Condition::LE => "<=",
+            Condition::IN => "IN",
+            Condition::NIN => "NOT IN",
+            Condition::LIKE => "LIKE",
+            Condition::ILIKE => "ILIKE",
+            Condition::SW => "LIKE",
+            Condition::EW => "LIKE",
+            Condition::CT => "LIKE",
+            Condition::NULL => "IS NULL",
+            Condition::NOTNULL => "IS NOT NULL",
+            Condition::BETWEEN => "BETWEEN",
+        }
+    }
+
+    fn is_like(&self) -> bool {
+        matches!(
+            self,
+            Condition::LIKE | Condition::ILIKE | Condition::SW | Condition::EW | Condition::CT
+        )
+    }
+
+    fn is_valueless(&self) -> bool {
+        matches!(self, Condition::NULL | Condition::NOTNULL)
+    }
+
+    fn is_list(&self) -> bool {
+        matches!(self, Condition::IN | Condition::NIN)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FilterValue {
+    Single(String),
+    // filter[]=status-in-pending,shipped
+    List(Vec<String>),
+    // filter[]=createdAt-between-2024-01-01,2024-02-01
+    Range(String, String),
+    // filter[]=deletedAt-null
+    None,
+}
+
+impl From<&str> for FilterValue {
+    fn from(value: &str) -> Self {
+        Self::Single(value.into())
+    }
+}
+
+impl From<String> for FilterValue {
+    fn from(value: String) -> Self {
+        Self::Single(value)
+    }
+}
+
+impl std::fmt::Display for FilterValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FilterValue::Single(value) => write!(f, "{}", value),
+            FilterValue::List(values) => write!(f, "({})", values.join(", ")),
+            FilterValue::Range(from, to) => write!(f, "{} AND {}", from, to),
+            FilterValue::None => Ok(()),
         }
     }
 }
 
 // filter[]=field-gr-0 -> some_value > 0
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct Filter {
     pub field: String,
     pub condition: Condition,
-    pub value: String,
+    pub value: FilterValue,
+}
+
+impl std::fmt::Display for Filter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.field, self.condition.as_str())?;
+        match self.value {
+            FilterValue::None => Ok(()),
+            _ => write!(f, " {}", self.value),
+        }
+    }
 }
 
 impl Filter {
+    /// Parses a `field-condition-value` filter. Each part is percent-decoded after splitting, so
+    /// `%2D` can be used for a `-` that isn't a delimiter. The value of an `in` or `nin` filter is a
+    /// comma separated list, use `%2C` for a `,` that isn't a delimiter. `null` and `notnull`
+    /// filters don't take a value, eg `deletedAt-null`. A `between` filter takes two comma separated
+    /// values, eg `createdAt-between-2024-01-01,2024-02-01`.
     pub fn new(str: &str) -> Result<Self, ParseError> {
         let (field, rest) = match str.split_once("-") {
             Some(s) => s,
-            None => Err(ParseError::InvalidFilter)?,
+            None => Err(ErrorKind::InvalidFilter)?,
         };
 
         let (condition, value) = match rest.split_once("-") {
-            Some(s) => s,
-            None => Err(ParseError::InvalidFilter)?,
+            Some((condition, value)) => (condition, Some(value)),
+            None => (rest, None),
+        };
+
+        let condition: Condition = decode(condition).parse()?;
+
+        let value = match value {
+            None if condition.is_valueless() => FilterValue::None,
+            Some(value) if condition.is_list() => {
+                let values: Vec<String> = value.split(',').map(decode).collect();
+                if values.iter().any(|v| v.is_empty()) {
+                    Err(ErrorKind::InvalidFilter)?
+                }
+
+                FilterValue::List(values)
+            }
+            Some(value) if condition == Condition::BETWEEN => match value.split_once(',') {
+                Some((from, to)) if !from.is_empty() && !to.is_empty() && !to.contains(',') => {
+                    FilterValue::Range(decode(from), decode(to))
+                }
+                _ => Err(ErrorKind::InvalidRange)?,
+            },
+            Some(value) if !condition.is_valueless() => FilterValue::Single(decode(value)),
+            _ => Err(ErrorKind::InvalidFilter)?,
         };
 
         Ok(Self {
-            field: field.into(),
-            condition: condition.parse()?,
-            value: value.into(),
+            field: decode(field),
+            condition,
+            value,
         })
     }
 
+    /// Returns an equivalent filter in a canonical form: the values of an `in` or `nin` filter are
+    /// sorted and deduplicated, and a list of one value becomes an `eq` or `ne` filter.
+    pub fn normalized(&self) -> Self {
+        let mut values = match self.value {
+            FilterValue::List(ref values) => values.clone(),
+            _ => return self.clone(),
+        };
+        values.sort();
+        values.dedup();
+
+        let (condition, value) = match (self.condition, values.len()) {
+            (Condition::IN, 1) => (Condition::EQ, FilterValue::Single(values.remove(0))),
+            (Condition::NIN, 1) => (Condition::NE, FilterValue::Single(values.remove(0))),
+            (condition, _) => (condition, FilterValue::List(values)),
+        };
+
+        Self {
+            field: self.field.clone(),
+            condition,
+            value,
+        }
+    }
+
+    /// Returns the filter as it's written in a url query, the inverse of [`Filter::new`].
+    pub(crate) fn to_query(&self) -> String {
+        self.to_query_field(&encode_field(&self.field))
+    }
+
+    /// Returns the filter as it's written in a url query with an already encoded field.
+    pub(crate) fn to_query_field(&self, field: &str) -> String {
+        let mut query = String::from(field);
+        query.push('-');
+        query.push_str(self.condition.name());
+
+        match &self.value {
+            FilterValue::Single(value) => {
+                query.push('-');
+                query.push_str(&encode(value));
+            }
+            FilterValue::List(values) => {
+                query.push('-');
+                let values: Vec<String> = values.iter().map(|v| encode(v)).collect();
+                query.push_str(&values.join(","));
+            }
+            FilterValue::Range(from, to) => {
+                query.push('-');
+                query.push_str(&encode(from));
+                query.push(',');
+                query.push_str(&encode(to));
+            }
+            FilterValue::None => {}
+        }
+
+        query
+    }
+
     pub fn from_key_value(key: &str, value: &str, condition: Condition) -> Self {
         Self {
             field: key.into(),
@@ -78,42 +283,96 @@ impl Filter {
         }
     }
 
-    pub fn to_string(&self) -> String {
-        let mut filter = String::new();
-        filter.push_str(&self.field);
-        filter.push_str(" ");
-        filter.push_str(self.condition.as_str());
-        filter.push_str(" ");
-        filter.push_str(&self.value);
+    /// Returns the values to bind for this filter, one per placeholder, parsed as `field_type`.
+    /// With `array_binds`, a list is bound as a single [`Value::List`]. The values of `sw`, `ew`
+    /// and `ct` filters are escaped and wrapped in wildcards, eg `ct-50%` is bound as `%50\%%`.
+    pub fn values(&self, field_type: Option<&FieldType>, array_binds: bool) -> Vec<Value> {
+        let value = |value: &str| Value::parse_or_string(field_type, value);
 
-        filter
+        match &self.value {
+            FilterValue::None => Vec::new(),
+            FilterValue::Range(from, to) => vec![value(from), value(to)],
+            FilterValue::Single(pattern) if self.condition.is_like() => {
+                let pattern = match self.condition {
+                    Condition::SW => format!("{}%", escape_like(pattern)),
+                    Condition::EW => format!("%{}", escape_like(pattern)),
+                    Condition::CT => format!("%{}%", escape_like(pattern)),
+                    _ => pattern.to_owned(),
+                };
+
+                // Patterns are always strings
+                vec![Value::String(pattern)]
+            }
+            FilterValue::Single(v) => vec![value(v)],
+            FilterValue::List(values) if array_binds => {
+                vec![Value::List(values.iter().map(|v| value(v)).collect())]
+            }
+            FilterValue::List(values) => values.iter().map(|v| value(v)).collect(),
+        }
     }
 
-    fn to_sql(
+    /// Returns the SQL for this filter against an already rendered column.
+    pub(crate) fn to_sql(
         &self,
-        mut filter: String,
+        column: String,
         idx: usize,
-        case: Option<Case>,
-        database: &Database,
+        dialect: &dyn Dialect,
+        array_binds: bool,
     ) -> String {
-        // Check if we need to convert case
-        match case {
-            Some(case) => filter.push_str(&self.field.to_case(case)),
-            None => filter.push_str(&self.field),
-        }
-
-        // Push the comparison operator
-        filter.push_str(" ");
-        filter.push_str(self.condition.as_str());
-        filter.push_str(" ");
-
-        // Push the parameters
-        match database {
-            Database::Postgres => {
-                filter.push_str("$");
-                filter.push_str(&idx.to_string());
+        let mut filter = column;
+        match &self.value {
+            // deleted_at IS NULL
+            FilterValue::None => {
+                filter.push(' ');
+                filter.push_str(self.condition.as_str());
+            }
+            // created_at BETWEEN $1 AND $2
+            FilterValue::Range(_, _) => {
+                filter.push(' ');
+                filter.push_str(self.condition.as_str());
+                filter.push(' ');
+                dialect.push_placeholder(&mut filter, idx);
+                filter.push_str(" AND ");
+                dialect.push_placeholder(&mut filter, idx + 1);
+            }
+            // status = ANY($1)
+            FilterValue::List(_) if array_binds => {
+                match self.condition {
+                    Condition::NIN => filter.push_str(" <> ALL("),
+                    _ => filter.push_str(" = ANY("),
+                }
+                dialect.push_placeholder(&mut filter, idx);
+                filter.push(')');
+            }
+            // status IN ($1, $2)
+            FilterValue::List(values) => {
+                filter.push(' ');
+                filter.push_str(self.condition.as_str());
+                filter.push_str(" (");
+                for i in 0..values.len() {
+                    if i > 0 {
+                        filter.push_str(", ");
+                    }
+                    dialect.push_placeholder(&mut filter, idx + i);
+                }
+                filter.push(')');
+            }
+            FilterValue::Single(_) if self.condition.is_like() => {
+                let column = std::mem::take(&mut filter);
+                let case_insensitive = self.condition == Condition::ILIKE;
+                // The values of sw, ew and ct filters are escaped with a backslash
+                let escape = match self.condition {
+                    Condition::SW | Condition::EW | Condition::CT => Some('\\'),
+                    _ => None,
+                };
+                dialect.push_like(&mut filter, &column, idx, case_insensitive, escape);
+            }
+            FilterValue::Single(_) => {
+                filter.push(' ');
+                filter.push_str(self.condition.as_str());
+                filter.push(' ');
+                dialect.push_placeholder(&mut filter, idx);
             }
-            Database::MySQL => filter.push_str("?"),
         }
 
         filter
@@ -124,26 +383,166 @@ impl Filter {
         idx: usize,
         table: Option<&&str>,
         case: Option<Case>,
-        database: &Database,
+        dialect: &dyn Dialect,
+        array_binds: bool,
     ) -> String {
-        let mut filter = String::new();
-        if let Some(table) = table {
-            filter.push_str(table);
-            filter.push_str(".")
-        }
+        let column = column(table, &self.field, case, dialect);
 
-        self.to_sql(filter, idx, case, &database)
+        self.to_sql(column, idx, dialect, array_binds)
     }
 }
 
+// Escapes LIKE wildcards so they match literally
+fn escape_like(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        if matches!(c, '\\' | '%' | '_') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+
+    escaped
+}
+
 #[cfg(test)]
 mod test {
-    use super::Filter;
+    use crate::{dialect::Database, schema::FieldType, value::Value, ErrorKind};
+
+    use super::{Condition, Filter, FilterValue};
 
     #[test]
     fn test_new_uuid() {
         let filter = Filter::new("id-eq-8bd8a6fb-e2b2-47ab-b3db-4f47c067ba5e").unwrap();
 
-        assert_eq!(filter.value, "8bd8a6fb-e2b2-47ab-b3db-4f47c067ba5e");
+        assert_eq!(filter.value, "8bd8a6fb-e2b2-47ab-b3db-4f47c067ba5e".into());
+    }
+
+    #[test]
+    fn test_new_escaped_delimiter() {
+        let filter = Filter::new("sku%2Did-eq-a%2Db+c%25").unwrap();
+
+        assert_eq!(filter.field, "sku-id");
+        assert_eq!(filter.value, "a-b c%".into());
+    }
+
+    #[test]
+    fn test_new_in() {
+        let filter = Filter::new("status-in-pending,shipped,a%2Cb").unwrap();
+
+        assert_eq!(filter.condition, Condition::IN);
+        assert_eq!(
+            filter.value,
+            FilterValue::List(vec!["pending".into(), "shipped".into(), "a,b".into()])
+        );
+
+        assert!(Filter::new("status-nin-pending,").is_err());
+    }
+
+    #[test]
+    fn test_to_sql_like() {
+        let filter = Filter::new("name-ilike-bob%").unwrap();
+
+        let sql = filter.to_sql_map_table(1, Some(&"users"), None, &Database::Postgres, false);
+        assert_eq!(sql, "\"users\".\"name\" ILIKE $1");
+
+        let sql = filter.to_sql_map_table(1, Some(&"users"), None, &Database::MySQL, false);
+        assert_eq!(sql, "LOWER(`users`.`name`) LIKE LOWER(?)");
+
+        let sql = filter.to_sql_map_table(1, Some(&"users"), None, &Database::Sqlite, false);
+        assert_eq!(sql, "\"users\".\"name\" LIKE ?1");
+        assert_eq!(filter.values(None, false), [Value::from("bob%")]);
+
+        let filter = Filter::new("name-ct-50%25_off").unwrap();
+
+        let sql = filter.to_sql_map_table(1, None, None, &Database::Postgres, false);
+        assert_eq!(sql, "\"name\" LIKE $1");
+        assert_eq!(filter.values(None, false), [Value::from("%50\\%\\_off%")]);
+
+        let sql = filter.to_sql_map_table(1, None, None, &Database::Sqlite, false);
+        assert_eq!(sql, "\"name\" LIKE ?1 ESCAPE '\\'");
+
+        let sql = filter.to_sql_map_table(1, None, None, &Database::MsSql, false);
+        assert_eq!(sql, "[name] LIKE REPLACE(@p1, '[', '\\[') ESCAPE '\\'");
+
+        let filter = Filter::new("name-ilike-bob%").unwrap();
+        let sql = filter.to_sql_map_table(1, None, None, &Database::MsSql, false);
+        assert_eq!(sql, "LOWER([name]) LIKE LOWER(@p1)");
+
+        let filter = Filter::new("name-sw-bo").unwrap();
+        assert_eq!(filter.values(None, false), [Value::from("bo%")]);
+
+        let filter = Filter::new("name-ew-ob").unwrap();
+        assert_eq!(filter.values(None, false), [Value::from("%ob")]);
+    }
+
+    #[test]
+    fn test_new_null() {
+        let filter = Filter::new("deletedAt-null").unwrap();
+        assert_eq!(filter.condition, Condition::NULL);
+        assert_eq!(filter.value, FilterValue::None);
+        assert!(filter.values(None, false).is_empty());
+
+        let sql = filter.to_sql_map_table(1, None, None, &Database::Postgres, false);
+        assert_eq!(sql, "\"deletedAt\" IS NULL");
+
+        let filter = Filter::new("deletedAt-notnull").unwrap();
+        assert_eq!(filter.to_string(), "deletedAt IS NOT NULL");
+
+        assert!(Filter::new("deletedAt-null-1").is_err());
+        assert!(Filter::new("deletedAt-eq").is_err());
+    }
+
+    #[test]
+    fn test_new_between() {
+        let filter = Filter::new("createdAt-between-2024-01-01,2024-02-01").unwrap();
+        assert_eq!(
+            filter.value,
+            FilterValue::Range("2024-01-01".into(), "2024-02-01".into())
+        );
+
+        let sql = filter.to_sql_map_table(2, None, None, &Database::Postgres, false);
+        assert_eq!(sql, "\"createdAt\" BETWEEN $2 AND $3");
+        assert_eq!(
+            filter.values(Some(&FieldType::Date), false),
+            [
+                Value::Date("2024-01-01".into()),
+                Value::Date("2024-02-01".into())
+            ]
+        );
+
+        for invalid in [
+            "price-between-1",
+            "price-between-1,",
+            "price-between-,2",
+            "price-between-1,2,3",
+        ] {
+            assert_eq!(
+                Filter::new(invalid).map_err(|e| e.kind),
+                Err(ErrorKind::InvalidRange)
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_sql_in() {
+        let filter = Filter::new("status-nin-pending,shipped").unwrap();
+
+        let sql = filter.to_sql_map_table(3, None, None, &Database::Postgres, false);
+        assert_eq!(sql, "\"status\" NOT IN ($3, $4)");
+        assert_eq!(filter.values(None, false).len(), 2);
+
+        let sql = filter.to_sql_map_table(3, None, None, &Database::MySQL, false);
+        assert_eq!(sql, "`status` NOT IN (?, ?)");
+
+        let sql = filter.to_sql_map_table(3, None, None, &Database::Sqlite, false);
+        assert_eq!(sql, "\"status\" NOT IN (?3, ?4)");
+
+        let sql = filter.to_sql_map_table(3, None, None, &Database::Postgres, true);
+        assert_eq!(sql, "\"status\" <> ALL($3)");
+        assert_eq!(
+            filter.values(None, true),
+            vec![Value::List(vec!["pending".into(), "shipped".into()])]
+        );
     }
 }
@@ -0,0 +1,211 @@
+use std::str::FromStr;
+
+use convert_case::Case;
+
+use crate::{
+    dialect::{self, Dialect},
+    encoding::encode_field,
+    filter::Filter,
+    schema::FieldType,
+    ErrorKind, ParseError,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Aggregate {
+    COUNT,
+    SUM,
+    AVG,
+    MIN,
+    MAX,
+}
+
+impl FromStr for Aggregate {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "count" => Ok(Aggregate::COUNT),
+            "sum" => Ok(Aggregate::SUM),
+            "avg" => Ok(Aggregate::AVG),
+            "min" => Ok(Aggregate::MIN),
+            "max" => Ok(Aggregate::MAX),
+            _ => Err(ParseError::new(ErrorKind::InvalidAggregate)
+                .expected(["count", "sum", "avg", "min", "max"])),
+        }
+    }
+}
+
+impl Aggregate {
+    /// Returns the aggregate as it's written in a url query, eg `sum`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Aggregate::COUNT => "count",
+            Aggregate::SUM => "sum",
+            Aggregate::AVG => "avg",
+            Aggregate::MIN => "min",
+            Aggregate::MAX => "max",
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            Aggregate::COUNT => "COUNT",
+            Aggregate::SUM => "SUM",
+            Aggregate::AVG => "AVG",
+            Aggregate::MIN => "MIN",
+            Aggregate::MAX => "MAX",
+        }
+    }
+
+    /// Returns the type of the aggregate of a field of `field_type`.
+    pub fn value_type<'a>(&self, field_type: Option<&'a FieldType>) -> Option<&'a FieldType> {
+        match self {
+            Aggregate::COUNT => Some(&FieldType::Int),
+            // The average of an int column isn't an int
+            Aggregate::AVG => Some(&FieldType::Float),
+            _ => field_type,
+        }
+    }
+}
+
+// having[]=count-gt-5 -> COUNT(*) > 5, having[]=sum(price)-ge-100 -> SUM(price) >= 100
+#[derive(Debug, Clone, PartialEq)]
+pub struct Having {
+    pub aggregate: Aggregate,
+    /// The aggregated field, `None` for `count` which counts every row.
+    pub field: Option<String>,
+    /// The filter applied to the aggregate, its field is the aggregate as written in the query.
+    pub filter: Filter,
+}
+
+impl Having {
+    /// Parses an `aggregate-condition-value` filter where the aggregate is either `count` or one of
+    /// `count`, `sum`, `avg`, `min` or `max` applied to a field, eg `sum(price)`.
+    pub fn new(str: &str) -> Result<Self, ParseError> {
+        let filter = Filter::new(str)?;
+
+        let (aggregate, field) = match filter.field.split_once('(') {
+            Some((aggregate, field)) => match field.strip_suffix(')') {
+                Some(field) if !field.is_empty() => (aggregate, Some(field.to_owned())),
+                _ => Err(ErrorKind::InvalidAggregate)?,
+            },
+            None if filter.field == "count" => ("count", None),
+            None => Err(ErrorKind::InvalidAggregate)?,
+        };
+
+        Ok(Self {
+            aggregate: aggregate.parse()?,
+            field,
+            filter,
+        })
+    }
+
+    /// Returns an equivalent filter with its values normalized like [`Filter::normalized`].
+    pub fn normalized(&self) -> Self {
+        Self {
+            aggregate: self.aggregate.clone(),
+            field: self.field.clone(),
+            filter: self.filter.normalized(),
+        }
+    }
+
+    /// Returns the filter as it's written in a url query, the inverse of [`Having::new`].
+    pub(crate) fn to_query(&self) -> String {
+        let aggregate = match self.field {
+            Some(ref field) => format!("{}({})", self.aggregate.name(), encode_field(field)),
+            None => String::from("count"),
+        };
+
+        self.filter.to_query_field(&aggregate)
+    }
+
+    pub fn to_sql_map_table(
+        &self,
+        idx: usize,
+        table: Option<&&str>,
+        case: Option<Case>,
+        dialect: &dyn Dialect,
+        array_binds: bool,
+    ) -> String {
+        let column = self
+            .field
+            .as_ref()
+            .map(|field| dialect::column(table, field, case, dialect));
+
+        self.to_sql(column, idx, dialect, array_binds)
+    }
+
+    /// Returns the SQL for this filter with the aggregated field already rendered as `column`.
+    pub(crate) fn to_sql(
+        &self,
+        column: Option<String>,
+        idx: usize,
+        dialect: &dyn Dialect,
+        array_binds: bool,
+    ) -> String {
+        let mut aggregate = String::from(self.aggregate.as_str());
+        aggregate.push('(');
+        match column {
+            Some(column) => aggregate.push_str(&column),
+            None => aggregate.push('*'),
+        }
+        aggregate.push(')');
+
+        self.filter.to_sql(aggregate, idx, dialect, array_binds)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{sql::Database, value::Value, ErrorKind};
+
+    use super::{Aggregate, Having};
+
+    #[test]
+    fn test_new() {
+        let having = Having::new("count-gt-5").unwrap();
+        assert_eq!(having.aggregate, Aggregate::COUNT);
+        assert_eq!(having.field, None);
+
+        let having = Having::new("sum(totalPrice)-ge-100").unwrap();
+        assert_eq!(having.aggregate, Aggregate::SUM);
+        assert_eq!(having.field, Some("totalPrice".into()));
+
+        for invalid in [
+            "price-gt-5",
+            "sum()-gt-5",
+            "sum(price-gt-5",
+            "drop(price)-gt-5",
+        ] {
+            assert_eq!(
+                Having::new(invalid).map_err(|e| e.kind),
+                Err(ErrorKind::InvalidAggregate)
+            );
+        }
+    }
+
+    #[test]
+    fn test_to_sql() {
+        let having = Having::new("avg(totalPrice)-between-10,20").unwrap();
+
+        let sql = having.to_sql_map_table(
+            3,
+            Some(&"orders"),
+            Some(convert_case::Case::Snake),
+            &Database::Postgres,
+            false,
+        );
+        assert_eq!(sql, "AVG(\"orders\".\"total_price\") BETWEEN $3 AND $4");
+
+        let having = Having::new("count-gt-5").unwrap();
+
+        let sql = having.to_sql_map_table(1, None, None, &Database::MySQL, false);
+        assert_eq!(sql, "COUNT(*) > ?");
+        assert_eq!(
+            having
+                .filter
+                .values(having.aggregate.value_type(None), false),
+            [Value::I64(5)]
+        );
+    }
+}
@@ -0,0 +1,37 @@
+// Just enough JSON to write error responses without a serializer
+
+/// Pushes the string as a quoted JSON string.
+pub(crate) fn push_str(json: &mut String, str: &str) {
+    json.push('"');
+    for c in str.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            '\n' => json.push_str("\\n"),
+            '\r' => json.push_str("\\r"),
+            '\t' => json.push_str("\\t"),
+            c if (c as u32) < 0x20 => json.push_str(&format!("\\u{:04x}", c as u32)),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+pub(crate) fn push_option_str(json: &mut String, str: Option<&str>) {
+    match str {
+        Some(str) => push_str(json, str),
+        None => json.push_str("null"),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::push_str;
+
+    #[test]
+    fn test_push_str() {
+        let mut json = String::new();
+        push_str(&mut json, "a \"b\"\\c\n\u{1}é");
+        assert_eq!(json, r#""a \"b\"\\c\n\u0001é""#);
+    }
+}
@@ -1,31 +1,301 @@
-#[macro_use]
-
+#[cfg(feature = "sqlx")]
+pub mod bind;
+pub mod cursor;
+pub mod dialect;
+mod encoding;
+pub mod expr;
+#[cfg(any(feature = "actix", feature = "axum"))]
+pub mod extract;
 pub mod filter;
+pub mod having;
+mod json;
+pub mod schema;
 pub mod sort;
 pub mod sql;
 pub mod url_query;
+pub mod value;
 
 pub use url_query::UrlQuery;
 
-#[derive(Debug, PartialEq)]
-pub enum ParseError {
+/// Derives [`schema::Queryable`] for a struct.
+#[cfg(feature = "derive")]
+pub use query_derive::Queryable;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorKind {
     InvalidSort,
     InvalidSortBy,
     InvalidFilter,
     InvalidCondition,
     InvalidField,
+    InvalidLimit,
+    InvalidOffset,
+    InvalidRange,
+    InvalidNulls,
+    InvalidAggregate,
+    InvalidCursor,
+    /// The value can't be parsed as the field's type.
+    InvalidValue,
+    /// The field can't be filtered with the condition.
+    ConditionNotAllowed,
+    FieldNotSortable,
+    FieldNotGroupable,
+    /// A required parameter is missing.
+    Required,
+}
+
+impl ErrorKind {
+    pub fn as_str(&self) -> &str {
+        match self {
+            ErrorKind::InvalidSort => "invalid sort",
+            ErrorKind::InvalidSortBy => "invalid sort by",
+            ErrorKind::InvalidFilter => "invalid filter",
+            ErrorKind::InvalidCondition => "invalid filter condition",
+            ErrorKind::InvalidField => "invalid field",
+            ErrorKind::InvalidLimit => "invalid limit",
+            ErrorKind::InvalidOffset => "invalid offset",
+            ErrorKind::InvalidRange => "invalid range",
+            ErrorKind::InvalidNulls => "invalid nulls",
+            ErrorKind::InvalidAggregate => "invalid aggregate",
+            ErrorKind::InvalidCursor => "invalid cursor",
+            ErrorKind::InvalidValue => "invalid value",
+            ErrorKind::ConditionNotAllowed => "condition not allowed",
+            ErrorKind::FieldNotSortable => "field not sortable",
+            ErrorKind::FieldNotGroupable => "field not groupable",
+            ErrorKind::Required => "required",
+        }
+    }
+
+    /// Returns a stable identifier for the kind of error, eg `invalid_field`.
+    pub fn code(&self) -> &str {
+        match self {
+            ErrorKind::InvalidSort => "invalid_sort",
+            ErrorKind::InvalidSortBy => "invalid_sort_by",
+            ErrorKind::InvalidFilter => "invalid_filter",
+            ErrorKind::InvalidCondition => "invalid_condition",
+            ErrorKind::InvalidField => "invalid_field",
+            ErrorKind::InvalidLimit => "invalid_limit",
+            ErrorKind::InvalidOffset => "invalid_offset",
+            ErrorKind::InvalidRange => "invalid_range",
+            ErrorKind::InvalidNulls => "invalid_nulls",
+            ErrorKind::InvalidAggregate => "invalid_aggregate",
+            ErrorKind::InvalidCursor => "invalid_cursor",
+            ErrorKind::InvalidValue => "invalid_value",
+            ErrorKind::ConditionNotAllowed => "condition_not_allowed",
+            ErrorKind::FieldNotSortable => "field_not_sortable",
+            ErrorKind::FieldNotGroupable => "field_not_groupable",
+            ErrorKind::Required => "required",
+        }
+    }
+}
+
+/// An error parsing a url query, with as much context as is known about where it happened.
+///
+/// # Examples
+///
+/// ```
+/// use query::{ErrorKind, UrlQuery};
+///
+/// let err = UrlQuery::new("limit=10&filter[]=price-gt-1", ["userId"]).unwrap_err();
+///
+/// assert_eq!(err.kind, ErrorKind::InvalidField);
+/// assert_eq!(err.key.as_deref(), Some("filter[]"));
+/// assert_eq!(err.value.as_deref(), Some("price-gt-1"));
+/// assert_eq!(err.position, Some(9));
+/// assert_eq!(err.field.as_deref(), Some("price"));
+/// assert_eq!(err.expected, ["userId"]);
+/// assert_eq!(
+///     err.to_string(),
+///     "invalid field price in filter[]=price-gt-1 at position 9, expected one of: userId"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub kind: ErrorKind,
+    /// The decoded key of the parameter, eg `filter[]`.
+    pub key: Option<String>,
+    /// The value of the parameter as it was written in the query string.
+    pub value: Option<String>,
+    /// The byte offset of the parameter in the query string.
+    pub position: Option<usize>,
+    /// The field the error is about.
+    pub field: Option<String>,
+    /// The values that would have been accepted, eg the allowed conditions of a field.
+    pub expected: Vec<String>,
+}
+
+impl ParseError {
+    pub fn new(kind: ErrorKind) -> Self {
+        Self {
+            kind,
+            key: None,
+            value: None,
+            position: None,
+            field: None,
+            expected: Vec::new(),
+        }
+    }
+
+    pub fn field(mut self, field: &str) -> Self {
+        self.field = Some(field.into());
+
+        self
+    }
+
+    pub fn expected<T>(mut self, expected: T) -> Self
+    where
+        T: IntoIterator,
+        T::Item: Into<String>,
+    {
+        self.expected = expected.into_iter().map(Into::into).collect();
+
+        self
+    }
+
+    /// Sets the parameter the error happened in, unless it's already set.
+    pub(crate) fn param(mut self, key: &str, value: &str, position: usize) -> Self {
+        if self.key.is_none() {
+            self.key = Some(key.into());
+            self.value = Some(value.into());
+            self.position = Some(position);
+        }
+
+        self
+    }
+}
+
+impl From<ErrorKind> for ParseError {
+    fn from(kind: ErrorKind) -> Self {
+        Self::new(kind)
+    }
 }
 
 impl std::fmt::Display for ParseError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            ParseError::InvalidSort => write!(f, "invalid sort"),
-            ParseError::InvalidSortBy => write!(f, "invalid sort by"),
-            ParseError::InvalidFilter => write!(f, "invalid filter"),
-            ParseError::InvalidCondition => write!(f, "invalid filter condition"),
-            ParseError::InvalidField => write!(f, "invalid field"),
+        if self.kind == ErrorKind::Required {
+            let name = self.field.as_ref().or(self.key.as_ref());
+            return match name {
+                Some(name) => write!(f, "{} is required", name),
+                None => write!(f, "{}", self.kind.as_str()),
+            };
+        }
+
+        write!(f, "{}", self.kind.as_str())?;
+        if let Some(ref field) = self.field {
+            write!(f, " {}", field)?;
         }
+        if let (Some(key), Some(value)) = (&self.key, &self.value) {
+            write!(f, " in {}={}", key, value)?;
+        }
+        if let Some(position) = self.position {
+            write!(f, " at position {}", position)?;
+        }
+        if !self.expected.is_empty() {
+            write!(f, ", expected one of: {}", self.expected.join(", "))?;
+        }
+
+        Ok(())
     }
 }
 
 impl std::error::Error for ParseError {}
+
+/// Every error found parsing a url query, in the order they appear in the query string.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseErrors {
+    pub errors: Vec<ParseError>,
+}
+
+impl ParseErrors {
+    pub fn iter(&self) -> std::slice::Iter<'_, ParseError> {
+        self.errors.iter()
+    }
+
+    /// Returns the errors as an RFC 7807 problem details JSON object, to be sent with a `400 Bad
+    /// Request` and a content type of `application/problem+json`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use query::UrlQuery;
+    ///
+    /// let errors = UrlQuery::parse_all("limit=x", &[].into()).unwrap_err();
+    ///
+    /// assert_eq!(
+    ///     errors.to_problem_json(),
+    ///     r#"{"type":"about:blank","title":"Invalid query string","status":400,"detail":"invalid limit in limit=x at position 0","errors":[{"code":"invalid_limit","detail":"invalid limit in limit=x at position 0","key":"limit","value":"x","position":0,"field":null,"expected":[]}]}"#
+    /// );
+    /// ```
+    pub fn to_problem_json(&self) -> String {
+        let mut json = String::from(
+            r#"{"type":"about:blank","title":"Invalid query string","status":400,"detail":"#,
+        );
+        json::push_str(&mut json, &self.to_string());
+        json.push_str(r#","errors":["#);
+        for (i, err) in self.errors.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            push_error_json(&mut json, err);
+        }
+        json.push_str("]}");
+
+        json
+    }
+}
+
+fn push_error_json(json: &mut String, err: &ParseError) {
+    json.push_str(r#"{"code":"#);
+    json::push_str(json, err.kind.code());
+    json.push_str(r#","detail":"#);
+    json::push_str(json, &err.to_string());
+    json.push_str(r#","key":"#);
+    json::push_option_str(json, err.key.as_deref());
+    json.push_str(r#","value":"#);
+    json::push_option_str(json, err.value.as_deref());
+    json.push_str(r#","position":"#);
+    match err.position {
+        Some(position) => json.push_str(&position.to_string()),
+        None => json.push_str("null"),
+    }
+    json.push_str(r#","field":"#);
+    json::push_option_str(json, err.field.as_deref());
+    json.push_str(r#","expected":["#);
+    for (i, expected) in err.expected.iter().enumerate() {
+        if i > 0 {
+            json.push(',');
+        }
+        json::push_str(json, expected);
+    }
+    json.push_str("]}");
+}
+
+impl From<ParseError> for ParseErrors {
+    fn from(err: ParseError) -> Self {
+        Self { errors: vec![err] }
+    }
+}
+
+impl<'a> IntoIterator for &'a ParseErrors {
+    type Item = &'a ParseError;
+    type IntoIter = std::slice::Iter<'a, ParseError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.iter()
+    }
+}
+
+impl std::fmt::Display for ParseErrors {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, err) in self.errors.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", err)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseErrors {}
@@ -0,0 +1,397 @@
+use std::collections::HashMap;
+
+use crate::filter::{Condition, Filter, FilterValue};
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FieldType {
+    Int,
+    Float,
+    Bool,
+    /// A hyphenated UUID, eg `8bd8a6fb-e2b2-47ab-b3db-4f47c067ba5e`.
+    Uuid,
+    /// An ISO 8601 date, eg `2024-01-31`.
+    Date,
+    /// An RFC 3339 timestamp, eg `2024-01-31T12:00:00Z`. The offset may be left out.
+    Timestamp,
+    String,
+    /// One of the given values.
+    Enum(Vec<String>),
+}
+
+impl FieldType {
+    /// Returns whether the value can be parsed as this type.
+    pub fn validate(&self, value: &str) -> bool {
+        match self {
+            FieldType::Int => value.parse::<i64>().is_ok(),
+            FieldType::Float => value.parse::<f64>().is_ok_and(f64::is_finite),
+            FieldType::Bool => value == "true" || value == "false",
+            FieldType::Uuid => is_uuid(value),
+            FieldType::Date => is_date(value),
+            FieldType::Timestamp => is_timestamp(value),
+            FieldType::String => true,
+            FieldType::Enum(values) => values.iter().any(|v| v == value),
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            FieldType::Int => "int",
+            FieldType::Float => "float",
+            FieldType::Bool => "bool",
+            FieldType::Uuid => "uuid",
+            FieldType::Date => "date",
+            FieldType::Timestamp => "timestamp",
+            FieldType::String => "string",
+            FieldType::Enum(_) => "enum",
+        }
+    }
+}
+
+/// Describes a field that can be used in a url query.
+///
+/// # Examples
+///
+/// ```
+/// use query::{filter::Condition, schema::{FieldSpec, FieldType}};
+///
+/// let price = FieldSpec::new("price", FieldType::Float)
+///     .conditions([Condition::EQ, Condition::GE, Condition::LE])
+///     .sortable(true)
+///     .column("unit_price")
+///     .table("orders");
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldSpec {
+    pub name: String,
+    pub field_type: FieldType,
+    /// The conditions the field can be filtered with, `None` allows every condition.
+    pub conditions: Option<Vec<Condition>>,
+    pub sortable: bool,
+    pub groupable: bool,
+    /// Whether the url query has to filter by the field.
+    pub required: bool,
+    /// The SQL column, used as is instead of converting the case of the name.
+    pub column: Option<String>,
+    /// The table of the column, used to qualify ambiguous columns.
+    pub table: Option<String>,
+}
+
+impl FieldSpec {
+    /// Returns a FieldSpec that can be filtered with every condition, but can't be sorted or
+    /// grouped by.
+    pub fn new(name: &str, field_type: FieldType) -> Self {
+        Self {
+            name: name.into(),
+            field_type,
+            conditions: None,
+            sortable: false,
+            groupable: false,
+            required: false,
+            column: None,
+            table: None,
+        }
+    }
+
+    pub fn conditions(mut self, conditions: impl IntoIterator<Item = Condition>) -> Self {
+        self.conditions = Some(conditions.into_iter().collect());
+
+        self
+    }
+
+    pub fn sortable(mut self, sortable: bool) -> Self {
+        self.sortable = sortable;
+
+        self
+    }
+
+    pub fn groupable(mut self, groupable: bool) -> Self {
+        self.groupable = groupable;
+
+        self
+    }
+
+    pub fn required(mut self, required: bool) -> Self {
+        self.required = required;
+
+        self
+    }
+
+    pub fn column(mut self, column: &str) -> Self {
+        self.column = Some(column.into());
+
+        self
+    }
+
+    pub fn table(mut self, table: &str) -> Self {
+        self.table = Some(table.into());
+
+        self
+    }
+
+    /// Returns whether the field can be filtered with the condition.
+    pub fn allows(&self, condition: &Condition) -> bool {
+        match self.conditions {
+            Some(ref conditions) => conditions.contains(condition),
+            None => true,
+        }
+    }
+
+    /// Returns whether every value of the filter can be parsed as the field's type.
+    pub fn validate(&self, filter: &Filter) -> bool {
+        validate_filter(&self.field_type, filter)
+    }
+}
+
+/// The fields that can be used in a url query.
+///
+/// A list of field names converts into a Schema of string fields that can be filtered with every
+/// condition, sorted and grouped by.
+///
+/// # Examples
+///
+/// ```
+/// use query::{schema::{FieldSpec, FieldType, Schema}, ErrorKind, UrlQuery};
+///
+/// let schema = Schema::new()
+///     .field(FieldSpec::new("userId", FieldType::Int))
+///     .field(FieldSpec::new("price", FieldType::Float).sortable(true));
+///
+/// let parsed = UrlQuery::with_schema("userId=1&sort=price-desc", &schema);
+/// assert!(parsed.is_ok());
+///
+/// let err = UrlQuery::with_schema("filter[]=price-ge-abc", &schema).unwrap_err();
+/// assert_eq!(err.kind, ErrorKind::InvalidValue);
+/// assert_eq!(err.field.as_deref(), Some("price"));
+/// assert_eq!(err.expected, ["float"]);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Schema {
+    fields: HashMap<String, FieldSpec>,
+}
+
+impl Schema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn field(mut self, field: FieldSpec) -> Self {
+        self.fields.insert(field.name.clone(), field);
+
+        self
+    }
+
+    pub fn get(&self, name: &str) -> Option<&FieldSpec> {
+        self.fields.get(name)
+    }
+
+    pub fn fields(&self) -> impl Iterator<Item = &FieldSpec> {
+        self.fields.values()
+    }
+}
+
+/// A type that describes the fields it can be queried by, usually implemented with
+/// `#[derive(Queryable)]` from the `derive` feature.
+///
+/// # Examples
+///
+/// ```
+/// use query::{schema::{FieldSpec, FieldType, Queryable, Schema}, UrlQuery};
+///
+/// struct Order {
+///     user_id: i64,
+/// }
+///
+/// impl Queryable for Order {
+///     fn schema() -> Schema {
+///         Schema::new().field(FieldSpec::new("userId", FieldType::Int).column("user_id"))
+///     }
+/// }
+///
+/// let parsed = UrlQuery::new("userId=1", Order::schema());
+/// assert!(parsed.is_ok());
+/// ```
+pub trait Queryable {
+    fn schema() -> Schema;
+}
+
+impl<'a, T> From<T> for Schema
+where
+    T: IntoIterator<Item = &'a str>,
+{
+    fn from(names: T) -> Self {
+        let mut schema = Schema::new();
+        for name in names {
+            schema = schema.field(
+                FieldSpec::new(name, FieldType::String)
+                    .sortable(true)
+                    .groupable(true),
+            );
+        }
+
+        schema
+    }
+}
+
+/// Returns whether every value of the filter can be parsed as `field_type`. Patterns aren't
+/// validated, `ct-12` is a valid filter for a field that can only hold numbers.
+pub(crate) fn validate_filter(field_type: &FieldType, filter: &Filter) -> bool {
+    match &filter.value {
+        FilterValue::Single(value) => match filter.condition {
+            Condition::LIKE | Condition::ILIKE => true,
+            Condition::SW | Condition::EW | Condition::CT => true,
+            _ => field_type.validate(value),
+        },
+        FilterValue::List(values) => values.iter().all(|v| field_type.validate(v)),
+        FilterValue::Range(from, to) => field_type.validate(from) && field_type.validate(to),
+        FilterValue::None => true,
+    }
+}
+
+fn is_uuid(value: &str) -> bool {
+    value.len() == 36
+        && value.char_indices().all(|(i, c)| match i {
+            8 | 13 | 18 | 23 => c == '-',
+            _ => c.is_ascii_hexdigit(),
+        })
+}
+
+fn is_digits(value: &str) -> bool {
+    !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())
+}
+
+// YYYY-MM-DD
+fn is_date(value: &str) -> bool {
+    let mut parts = value.split('-');
+    let (year, month, day) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(y), Some(m), Some(d), None) if y.len() == 4 && m.len() == 2 && d.len() == 2 => {
+            (y, m, d)
+        }
+        _ => return false,
+    };
+
+    if !is_digits(year) || !is_digits(month) || !is_digits(day) {
+        return false;
+    }
+
+    let year: u32 = year.parse().unwrap();
+    let month: u32 = month.parse().unwrap();
+    let day: u32 = day.parse().unwrap();
+
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+    let days = match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if leap => 29,
+        2 => 28,
+        _ => return false,
+    };
+
+    (1..=days).contains(&day)
+}
+
+// HH:MM:SS with optional fractional seconds
+fn is_time(value: &str) -> bool {
+    let (time, fraction) = match value.split_once('.') {
+        Some((time, fraction)) => (time, Some(fraction)),
+        None => (value, None),
+    };
+
+    if let Some(fraction) = fraction {
+        if !is_digits(fraction) {
+            return false;
+        }
+    }
+
+    let parts: Vec<&str> = time.split(':').collect();
+    match parts[..] {
+        [h, m, s] if h.len() == 2 && m.len() == 2 && s.len() == 2 => {
+            is_digits(h)
+                && is_digits(m)
+                && is_digits(s)
+                && h < "24"
+                && m < "60"
+                // Allow leap seconds
+                && s <= "60"
+        }
+        _ => false,
+    }
+}
+
+// YYYY-MM-DDTHH:MM:SS[.fff][Z|+HH:MM|-HH:MM]
+fn is_timestamp(value: &str) -> bool {
+    if value.len() < 19 || !value.is_char_boundary(10) {
+        return false;
+    }
+
+    let (date, rest) = value.split_at(10);
+    let rest = match rest.strip_prefix(['T', 't', ' ']) {
+        Some(rest) => rest,
+        None => return false,
+    };
+
+    let (time, offset) = match rest.find(['Z', 'z', '+', '-']) {
+        Some(i) => rest.split_at(i),
+        None => (rest, ""),
+    };
+
+    let offset = match offset {
+        "" | "Z" | "z" => true,
+        offset => match offset[1..].split_once(':') {
+            Some((h, m)) => {
+                h.len() == 2 && m.len() == 2 && is_digits(h) && is_digits(m) && h < "24" && m < "60"
+            }
+            None => false,
+        },
+    };
+
+    is_date(date) && is_time(time) && offset
+}
+
+#[cfg(test)]
+mod test {
+    use super::FieldType;
+
+    #[test]
+    fn test_validate() {
+        let valid = [
+            (FieldType::Int, "-12"),
+            (FieldType::Float, "1.5"),
+            (FieldType::Bool, "true"),
+            (FieldType::Uuid, "8bd8a6fb-e2b2-47ab-b3db-4f47c067ba5e"),
+            (FieldType::Date, "2024-02-29"),
+            (FieldType::Timestamp, "2024-01-31T12:00:00Z"),
+            (FieldType::Timestamp, "2024-01-31T12:00:00.123+01:00"),
+            (FieldType::Timestamp, "2024-01-31 12:00:00"),
+            (FieldType::String, "anything"),
+            (
+                FieldType::Enum(vec!["pending".into(), "shipped".into()]),
+                "shipped",
+            ),
+        ];
+
+        for (field_type, value) in valid {
+            assert!(field_type.validate(value), "{:?} {}", field_type, value);
+        }
+
+        let invalid = [
+            (FieldType::Int, "1.5"),
+            (FieldType::Float, "abc"),
+            (FieldType::Float, "NaN"),
+            (FieldType::Float, "inf"),
+            (FieldType::Float, "-infinity"),
+            (FieldType::Float, "1e999"),
+            (FieldType::Bool, "yes"),
+            (FieldType::Uuid, "8bd8a6fb-e2b2-47ab-b3db-4f47c067ba5"),
+            (FieldType::Date, "2023-02-29"),
+            (FieldType::Date, "2024-13-01"),
+            (FieldType::Timestamp, "2024-01-31"),
+            (FieldType::Timestamp, "2024-01-31T25:00:00Z"),
+            (FieldType::Timestamp, "2024-01-31T12:00:00+1"),
+            (FieldType::Enum(vec!["pending".into()]), "cancelled"),
+        ];
+
+        for (field_type, value) in invalid {
+            assert!(!field_type.validate(value), "{:?} {}", field_type, value);
+        }
+    }
+}
@@ -1,60 +1,94 @@
 use std::str::FromStr;
 
-use convert_case::{Case, Casing};
+use convert_case::Case;
 
-use crate::ParseError;
+use crate::{
+    dialect::{column, Dialect},
+    encoding::{decode, encode_field},
+    ErrorKind, ParseError,
+};
 
-// sort=field-desc
-#[derive(Debug, PartialEq)]
+// sort=field-desc or sort=field-desc-nullslast
+#[derive(Debug, Clone, PartialEq)]
 pub struct Sort {
     pub field: String,
     pub sort_by: SortBy,
+    pub nulls: Option<Nulls>,
+}
+
+impl std::fmt::Display for Sort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.field, self.sort_by.as_str())?;
+        match self.nulls {
+            Some(ref nulls) => write!(f, " {}", nulls.as_str()),
+            None => Ok(()),
+        }
+    }
 }
 
 impl Sort {
     pub fn new(str: &str) -> Result<Self, ParseError> {
-        let (field, sort_by) = str
+        let (field, rest) = str
             .split_once("-")
-            .map(|(f, s)| (f.to_owned(), s))
-            .ok_or_else(|| ParseError::InvalidSort)?;
+            .map(|(f, s)| (decode(f), s))
+            .ok_or(ErrorKind::InvalidSort)?;
 
-        let sort_by = SortBy::from_str(sort_by)?;
+        let (sort_by, nulls) = match rest.split_once("-") {
+            Some((sort_by, nulls)) => (sort_by, Some(Nulls::from_str(&decode(nulls))?)),
+            None => (rest, None),
+        };
 
-        Ok(Sort { field, sort_by })
-    }
+        let sort_by = SortBy::from_str(&decode(sort_by))?;
 
-    pub fn to_string(&self) -> String {
-        let mut sort = String::new();
-        sort.push_str(&self.field);
-        sort.push_str(" ");
-        sort.push_str(self.sort_by.as_str());
-
-        sort
+        Ok(Sort {
+            field,
+            sort_by,
+            nulls,
+        })
     }
 
-    pub fn to_sql(&self, mut sort: String, case: Option<Case>) -> String {
-        match case {
-            Some(case) => sort.push_str(&self.field.to_case(case)),
-            None => sort.push_str(&self.field.to_case(Case::Snake)),
+    /// Returns the sort as it's written in a url query, the inverse of [`Sort::new`].
+    pub(crate) fn to_query(&self) -> String {
+        let mut query = encode_field(&self.field);
+        query.push_str(match self.sort_by {
+            SortBy::ASC => "-asc",
+            SortBy::DESC => "-desc",
+        });
+        match self.nulls {
+            Some(Nulls::FIRST) => query.push_str("-nullsfirst"),
+            Some(Nulls::LAST) => query.push_str("-nullslast"),
+            None => {}
         }
-        sort.push_str(" ");
-        sort.push_str(self.sort_by.as_str());
 
-        sort
+        query
+    }
+
+    pub fn to_sql_map_table(
+        &self,
+        table: Option<&&str>,
+        case: Option<Case>,
+        dialect: &dyn Dialect,
+    ) -> String {
+        let column = column(
+            table,
+            &self.field,
+            Some(case.unwrap_or(Case::Snake)),
+            dialect,
+        );
+
+        self.to_sql(&column, dialect)
     }
 
-    pub fn to_sql_map_table(&self, table: Option<&&str>, case: Option<Case>) -> String {
+    /// Returns the SQL for this sort against an already rendered column.
+    pub(crate) fn to_sql(&self, column: &str, dialect: &dyn Dialect) -> String {
         let mut sort = String::new();
-        if let Some(table) = table {
-            sort.push_str(table);
-            sort.push_str(".")
-        }
+        dialect.push_sort(&mut sort, column, &self.sort_by, self.nulls.as_ref());
 
-        self.to_sql(sort, case)
+        sort
     }
 }
 
-#[derive(Debug, PartialEq)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum SortBy {
     ASC,
     DESC,
@@ -67,7 +101,7 @@ impl FromStr for SortBy {
         match s {
             "asc" => Ok(Self::ASC),
             "desc" => Ok(Self::DESC),
-            _ => Err(ParseError::InvalidSortBy),
+            _ => Err(ParseError::new(ErrorKind::InvalidSortBy).expected(["asc", "desc"])),
         }
     }
 }
@@ -80,3 +114,69 @@ impl SortBy {
         }
     }
 }
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Nulls {
+    FIRST,
+    LAST,
+}
+
+impl FromStr for Nulls {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "nullsfirst" => Ok(Self::FIRST),
+            "nullslast" => Ok(Self::LAST),
+            _ => {
+                Err(ParseError::new(ErrorKind::InvalidNulls).expected(["nullsfirst", "nullslast"]))
+            }
+        }
+    }
+}
+
+impl Nulls {
+    pub fn as_str(&self) -> &str {
+        match self {
+            Self::FIRST => "NULLS FIRST",
+            Self::LAST => "NULLS LAST",
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{dialect::Database, ErrorKind};
+
+    use super::{Nulls, Sort};
+
+    #[test]
+    fn test_new_nulls() {
+        let sort = Sort::new("shippedAt-desc-nullslast").unwrap();
+        assert_eq!(sort.nulls, Some(Nulls::LAST));
+        assert_eq!(sort.to_string(), "shippedAt DESC NULLS LAST");
+
+        let err = Sort::new("shippedAt-desc-nulls").unwrap_err();
+        assert_eq!(err.kind, ErrorKind::InvalidNulls);
+        assert_eq!(err.expected, ["nullsfirst", "nullslast"]);
+    }
+
+    #[test]
+    fn test_to_sql_nulls() {
+        let sort = Sort::new("shippedAt-desc-nullslast").unwrap();
+
+        let sql = sort.to_sql_map_table(Some(&"orders"), None, &Database::Postgres);
+        assert_eq!(sql, "\"orders\".\"shipped_at\" DESC NULLS LAST");
+
+        let sql = sort.to_sql_map_table(Some(&"orders"), None, &Database::MySQL);
+        assert_eq!(
+            sql,
+            "ISNULL(`orders`.`shipped_at`) ASC, `orders`.`shipped_at` DESC"
+        );
+
+        let sort = Sort::new("shippedAt-asc-nullsfirst").unwrap();
+
+        let sql = sort.to_sql_map_table(None, None, &Database::MySQL);
+        assert_eq!(sql, "ISNULL(`shipped_at`) DESC, `shipped_at` ASC");
+    }
+}
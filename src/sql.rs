@@ -1,13 +1,19 @@
 use std::collections::HashMap;
 
-use convert_case::{Case, Casing};
-
-use crate::UrlQuery;
-
-pub enum Database {
-    Postgres,
-    MySQL,
-}
+use convert_case::Case;
+
+#[cfg(feature = "sqlx")]
+use crate::bind::BindValue;
+pub use crate::dialect::Database;
+use crate::{
+    cursor::{Cursor, Direction},
+    dialect::{self, Dialect},
+    schema::{FieldType, Schema},
+    sort::{Nulls, Sort, SortBy},
+    url_query,
+    value::Value,
+    ErrorKind, ParseError, UrlQuery,
+};
 
 /// Generates an SQL query
 ///
@@ -21,17 +27,26 @@ pub enum Database {
 ///
 /// let parsed = UrlQuery::new(query, ["userId", "userName"]).unwrap();
 ///
-/// let (sql, args) = QueryBuilder::from_str("SELECT id, status FROM orders", parsed).build();
+/// let (sql, args) = QueryBuilder::from_str("SELECT id, status FROM orders", parsed)
+///     .build()
+///     .unwrap();
 ///
-/// assert_eq!(sql, "SELECT id, status FROM orders WHERE userId = $1 AND userName = $2");
+/// assert_eq!(sql, r#"SELECT id, status FROM orders WHERE "userId" = $1 AND "userName" = $2"#);
 /// assert_eq!(args.len(), 2);
 /// ```
 pub struct QueryBuilder<'a> {
     url_query: UrlQuery,
-    database: Database,
+    dialect: Box<dyn Dialect + 'a>,
     map_columns: HashMap<&'a str, &'a str>,
+    schema: Option<&'a Schema>,
     shift_bind: usize,
     convert_case: Option<Case>,
+    quote_identifiers: bool,
+    array_binds: bool,
+    default_limit: Option<u64>,
+    max_limit: Option<u64>,
+    default_sort: Option<Sort>,
+    tiebreaker: Option<&'a str>,
     sql: String,
 }
 
@@ -43,17 +58,24 @@ impl<'a> QueryBuilder<'a> {
     /// ```ignore
     /// use query::sql::QueryBuilder;
     ///
-    /// let (sql, args) = QueryBuilder::new("users", vec!["id", "first_name"], url_query).build();
+    /// let (sql, args) = QueryBuilder::new("users", vec!["id", "first_name"], url_query).build()?;
     /// ```
     pub fn new(table: &str, columns: Vec<&str>, url_query: UrlQuery) -> Self {
         let sql = gen_sql_select(table, columns);
 
         Self {
             url_query,
-            database: Database::Postgres,
+            dialect: Box::new(Database::Postgres),
             map_columns: HashMap::default(),
+            schema: None,
             shift_bind: 0,
             convert_case: None,
+            quote_identifiers: true,
+            array_binds: false,
+            default_limit: None,
+            max_limit: None,
+            default_sort: None,
+            tiebreaker: None,
             sql,
         }
     }
@@ -65,29 +87,43 @@ impl<'a> QueryBuilder<'a> {
     /// ```ignore
     /// use query::sql::QueryBuilder;
     ///
-    /// let (sql, args) = QueryBuilder::from_str("SELECT * FROM users", url_query).build();
+    /// let (sql, args) = QueryBuilder::from_str("SELECT * FROM users", url_query).build()?;
     /// ```
     pub fn from_str(sql: &str, url_query: UrlQuery) -> Self {
         Self {
             url_query,
-            database: Database::Postgres,
+            dialect: Box::new(Database::Postgres),
             map_columns: HashMap::default(),
+            schema: None,
             shift_bind: 0,
             convert_case: None,
+            quote_identifiers: true,
+            array_binds: false,
+            default_limit: None,
+            max_limit: None,
+            default_sort: None,
+            tiebreaker: None,
             sql: sql.into(),
         }
     }
 
     /// Set the database
     pub fn set_database(mut self, database: Database) -> Self {
-        self.database = database;
+        self.dialect = Box::new(database);
+
+        self
+    }
+
+    /// Set a dialect for a database that isn't covered by [`Database`].
+    pub fn set_dialect(mut self, dialect: impl Dialect + 'a) -> Self {
+        self.dialect = Box::new(dialect);
 
         self
     }
 
     /// Append anything to the SQL.
     pub fn append(mut self, sql: &str) -> Self {
-        self.sql.push_str(" ");
+        self.sql.push(' ');
         self.sql.push_str(sql);
 
         self
@@ -100,6 +136,14 @@ impl<'a> QueryBuilder<'a> {
         self
     }
 
+    /// Use the column and table of each field in the schema. Fields without a column in the schema
+    /// fall back to [`map_columns`](Self::map_columns) and [`convert_case`](Self::convert_case).
+    pub fn schema(mut self, schema: &'a Schema) -> Self {
+        self.schema = Some(schema);
+
+        self
+    }
+
     /// Shifts the number of the bind parameter for postgres. For example, if you call this
     /// method with a value of 1, the first arg you'll need to bind to the SQL will be $2.
     pub fn shift_bind(mut self, x: usize) -> Self {
@@ -114,26 +158,175 @@ impl<'a> QueryBuilder<'a> {
         self
     }
 
-    /// Append the WHERE clause to the SQL. Does nothing if there are no queries/filters in the url query.
-    pub fn append_where(&mut self) -> Vec<(String, String)> {
-        let mut args: Vec<(String, String)> = Vec::new();
+    /// Quotes table and column names, eg `"orders"."created_at"` on Postgres, so they can't be
+    /// mistaken for keywords. Enabled by default. Quoted names are case sensitive on Postgres, so a
+    /// column created as `createdAt` without quotes has to be filtered as `createdat`.
+    pub fn quote_identifiers(mut self, quote_identifiers: bool) -> Self {
+        self.quote_identifiers = quote_identifiers;
+
+        self
+    }
+
+    /// Binds the values of `in` and `nin` filters as a single Postgres array, eg `status = ANY($1)`,
+    /// instead of one arg per value. Has no effect on dialects that don't support array binds.
+    pub fn array_binds(mut self, array_binds: bool) -> Self {
+        self.array_binds = array_binds;
+
+        self
+    }
+
+    /// Sets the LIMIT to use when the url query doesn't include one.
+    pub fn default_limit(mut self, limit: u64) -> Self {
+        self.default_limit = Some(limit);
+
+        self
+    }
+
+    /// Caps the LIMIT at the given value. If neither the url query nor the default limit provide a
+    /// limit, this value is used.
+    pub fn max_limit(mut self, limit: u64) -> Self {
+        self.max_limit = Some(limit);
+
+        self
+    }
+
+    /// Sets the sort to use when the url query doesn't include one. SQL Server can only paginate
+    /// an ordered query, so a paginated query without a sort falls back to the default sort or the
+    /// [tiebreaker](Self::tiebreaker), and fails to build with [`BuildError::SortRequired`]
+    /// without either.
+    pub fn default_sort(mut self, sort: Sort) -> Self {
+        self.default_sort = Some(sort);
+
+        self
+    }
+
+    /// Sorts by a unique field, like the primary key, after the sorts of the url query so that rows
+    /// have a stable order even when the sorted fields have the same values. Keyset pagination with
+    /// a [`Cursor`] needs a tiebreaker to not skip or repeat rows, and every sorted field has to be
+    /// `NOT NULL` as a NULL can't be compared with the cursor.
+    pub fn tiebreaker(mut self, field: &'a str) -> Self {
+        self.tiebreaker = Some(field);
+
+        self
+    }
+
+    /// Returns the fields a [`Cursor`] holds the values of, in order: the sorted fields followed by
+    /// the tiebreaker. Encode the values of the last row of a page with [`Cursor::after`] to get
+    /// the next page, or of the first row with [`Cursor::before`] to get the previous one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use query::{cursor::Cursor, sql::QueryBuilder, UrlQuery};
+    ///
+    /// let parsed = UrlQuery::new("sort=createdAt-desc&limit=2", ["createdAt"]).unwrap();
+    ///
+    /// let builder = QueryBuilder::from_str("SELECT * FROM orders", parsed).tiebreaker("id");
+    /// assert_eq!(builder.cursor_fields(), ["createdAt", "id"]);
+    ///
+    /// let (sql, _) = builder.build().unwrap();
+    /// assert_eq!(
+    ///     sql,
+    ///     r#"SELECT * FROM orders ORDER BY "created_at" DESC, "id" DESC LIMIT 2"#
+    /// );
+    ///
+    /// // The last row of the page
+    /// let cursor = Cursor::after(["2024-01-31T12:00:00Z", "12"]);
+    ///
+    /// let query = format!("sort=createdAt-desc&limit=2&cursor={}", cursor.encode());
+    /// let parsed = UrlQuery::new(&query, ["createdAt"]).unwrap();
+    ///
+    /// let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+    ///     .tiebreaker("id")
+    ///     .build()
+    ///     .unwrap();
+    /// assert_eq!(
+    ///     sql,
+    ///     "SELECT * FROM orders WHERE (\"created_at\", \"id\") < ($1, $2) \
+    ///      ORDER BY \"created_at\" DESC, \"id\" DESC LIMIT 2"
+    /// );
+    /// assert_eq!(args.len(), 2);
+    /// ```
+    pub fn cursor_fields(&self) -> Vec<String> {
+        self.sorts().into_iter().map(|sort| sort.field).collect()
+    }
+
+    /// Checks that the cursor of the url query has a value for each of the
+    /// [`cursor_fields`](Self::cursor_fields) that's valid for the field's type in the
+    /// [`schema`](Self::schema), and that none of the sorts place NULLs first or last, which would
+    /// mean the field can be NULL. [`build`](Self::build) does this check, call
+    /// it before using the `append_*` methods on their own.
+    pub fn check_cursor(&self) -> Result<(), ParseError> {
+        let cursor = match self.url_query.cursor {
+            Some(ref cursor) => cursor,
+            None => return Ok(()),
+        };
+
+        let sorts = self.sorts();
+        if sorts.is_empty() || sorts.len() != cursor.values.len() {
+            Err(ParseError::new(ErrorKind::InvalidCursor).expected(self.cursor_fields()))?
+        }
+
+        if let Some(sort) = sorts.iter().find(|sort| sort.nulls.is_some()) {
+            Err(ParseError::new(ErrorKind::InvalidCursor).field(&sort.field))?
+        }
+
+        for (sort, value) in sorts.iter().zip(&cursor.values) {
+            if let Some(field_type) = self.field_type(&sort.field) {
+                if !field_type.validate(value) {
+                    Err(ParseError {
+                        kind: ErrorKind::InvalidCursor,
+                        ..url_query::invalid_value(&sort.field, field_type)
+                    })?
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Append the WHERE clause to the SQL and return the values to bind. Does nothing if there are
+    /// no queries/filters in the url query.
+    pub fn append_where(&mut self) -> Vec<Value> {
+        let mut args: Vec<Value> = Vec::new();
 
         // Filters:
+        let dialect = self.dialect();
+        let array_binds = self.array_binds && dialect.supports_array_binds();
         let mut filterv = Vec::new();
         for filter in self.url_query.filters.iter() {
-            let table = self.map_columns.get(filter.field.as_str());
-            filterv.push(filter.to_sql_map_table(
+            let column = self.column(&filter.field, self.convert_case, &dialect);
+            filterv.push(filter.to_sql(
+                column,
                 args.len() + self.shift_bind + 1,
-                table,
-                self.convert_case,
-                &self.database,
+                &dialect,
+                array_binds,
             ));
-            args.push((filter.field.to_owned(), filter.value.to_owned()));
+            args.extend(filter.values(self.field_type(&filter.field), array_binds));
         }
+
+        // Filter expressions:
+        let column = |field: &str| self.column(field, self.convert_case, &dialect);
+        for expr in self.url_query.exprs.iter() {
+            filterv.push(expr.to_sql_columns(
+                args.len() + self.shift_bind + 1,
+                &column,
+                &dialect,
+                array_binds,
+            ));
+            args.extend(expr.values(self.schema, array_binds));
+        }
+
+        // Keyset pagination:
+        if let Some(cursor) = self.cursor() {
+            let idx = args.len() + self.shift_bind + 1;
+            filterv.push(self.keyset(cursor, idx, &dialect, &mut args));
+        }
+
         let filter = filterv.join(" AND ");
 
         // WHERE clause
-        if filterv.len() > 0 {
+        if !filterv.is_empty() {
             self.sql.push_str(" WHERE ");
             self.sql.push_str(&filter);
         }
@@ -143,121 +336,451 @@ impl<'a> QueryBuilder<'a> {
 
     /// Append a GROUP BY to the SQL. Does nothing if there is no group in the url query.
     pub fn append_group(&mut self) {
-        if self.url_query.group.is_none() {
+        if self.url_query.group.is_empty() {
             return;
         };
 
-        let group = self.url_query.group.as_ref().unwrap();
+        let dialect = self.dialect();
+        let mut groupv = Vec::new();
+        for group in self.url_query.group.iter() {
+            groupv.push(self.column(group, self.convert_case, &dialect));
+        }
+
         self.sql.push_str(" GROUP BY ");
-        if let Some(table) = self.map_columns.get(group.as_str()) {
-            self.sql.push_str(table);
-            self.sql.push_str(".");
+        self.sql.push_str(&groupv.join(", "));
+    }
+
+    /// Append a HAVING clause to the SQL, pushing its values onto `args` so placeholders continue
+    /// on from the WHERE clause. Does nothing if there are no having filters in the url query.
+    pub fn append_having(&mut self, args: &mut Vec<Value>) {
+        let dialect = self.dialect();
+        let array_binds = self.array_binds && dialect.supports_array_binds();
+        let mut havingv = Vec::new();
+        for having in self.url_query.having.iter() {
+            let column = having
+                .field
+                .as_ref()
+                .map(|field| self.column(field, self.convert_case, &dialect));
+            havingv.push(having.to_sql(
+                column,
+                args.len() + self.shift_bind + 1,
+                &dialect,
+                array_binds,
+            ));
+            let field_type = having
+                .field
+                .as_ref()
+                .and_then(|field| self.field_type(field));
+            let field_type = having.aggregate.value_type(field_type);
+            args.extend(having.filter.values(field_type, array_binds));
         }
 
-        match self.convert_case {
-            Some(c) => self.sql.push_str(&group.to_case(c)),
-            None => self.sql.push_str(&group),
+        if !havingv.is_empty() {
+            self.sql.push_str(" HAVING ");
+            self.sql.push_str(&havingv.join(" AND "));
         }
     }
 
-    /// Append an ORDER BY to the SQL. Does nothing if there is no sort in the url query.
+    /// Append an ORDER BY to the SQL. Does nothing if there is no sort in the url query, no
+    /// default sort and no tiebreaker. The order is reversed for a cursor before a row, so that
+    /// the rows closest to the cursor are returned, and those rows have to be reversed again.
     pub fn append_sort(&mut self) {
-        if self.url_query.sort.is_none() {
+        let mut sorts = self.sorts();
+        if sorts.is_empty() {
             return;
         }
 
-        let sort = self.url_query.sort.as_ref().unwrap();
-        let table = self.map_columns.get(sort.field.as_str());
+        if let Some(Direction::Before) = self.cursor().map(|cursor| cursor.direction) {
+            for sort in sorts.iter_mut() {
+                sort.sort_by = match sort.sort_by {
+                    SortBy::ASC => SortBy::DESC,
+                    SortBy::DESC => SortBy::ASC,
+                };
+                sort.nulls = match sort.nulls {
+                    Some(Nulls::FIRST) => Some(Nulls::LAST),
+                    Some(Nulls::LAST) => Some(Nulls::FIRST),
+                    None => None,
+                };
+            }
+        }
+
+        let dialect = self.dialect();
+        let mut sortv = Vec::new();
+        for sort in sorts {
+            let column = self.sort_column(&sort.field, &dialect);
+            sortv.push(sort.to_sql(&column, &dialect));
+        }
+
         self.sql.push_str(" ORDER BY ");
-        self.sql
-            .push_str(&sort.to_sql_map_table(table, self.convert_case));
+        self.sql.push_str(&sortv.join(", "));
     }
 
-    /// Returns SQL statement along with a list of columns and args to bind.
-    pub fn build(mut self) -> (String, Vec<(String, String)>) {
+    /// Returns the SQL statement along with the values to bind, in placeholder order. Values are
+    /// parsed as the type of their field in the [`schema`](Self::schema), and are strings without
+    /// one.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`BuildError::Query`] if the cursor doesn't match the sorts, see
+    /// [`check_cursor`](Self::check_cursor), and [`BuildError::SortRequired`] if the query is
+    /// paginated without a sort on a dialect that requires one, like SQL Server, and there's no
+    /// default sort or tiebreaker to fall back to.
+    pub fn build(mut self) -> Result<(String, Vec<Value>), BuildError> {
+        self.check_cursor()?;
+
+        let paginated = self.limit().is_some() || self.offset().is_some();
+        if paginated && self.dialect.requires_sort_to_paginate() && self.sorts().is_empty() {
+            Err(BuildError::SortRequired)?
+        }
+
         // returns bind args
-        let args = self.append_where();
+        let mut args = self.append_where();
 
         self.append_group();
 
+        self.append_having(&mut args);
+
         self.append_sort();
 
-        // Limit & offset:
-        if let Ok(limit) = self.url_query.check_limit() {
-            append_limit(&mut self.sql, limit);
+        self.append_limit_offset();
+
+        Ok((self.sql, args))
+    }
+
+    /// Returns a sqlx QueryBuilder with the values already bound, so the query can be run with
+    /// `build()` or `build_query_as()`. The SQL is generated for `DB`, replacing the database or
+    /// dialect that was set.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`sqlx::Error::InvalidArgument`] if [`shift_bind`](Self::shift_bind) was used, as the
+    /// shifted placeholders would have to be bound before the values of the url query, or if
+    /// [`build`](Self::build) fails.
+    ///
+    /// # Examples
+    ///
+    /// ```ignore
+    /// let parsed = UrlQuery::with_schema(query, &schema)?;
+    ///
+    /// let orders: Vec<Order> = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+    ///     .schema(&schema)
+    ///     .build_sqlx::<Postgres>()?
+    ///     .build_query_as()
+    ///     .fetch_all(&pool)
+    ///     .await?;
+    /// ```
+    #[cfg(feature = "sqlx")]
+    pub fn build_sqlx<DB>(mut self) -> Result<sqlx::QueryBuilder<'static, DB>, sqlx::Error>
+    where
+        DB: BindValue,
+        DB::Arguments<'static>: sqlx::IntoArguments<'static, DB>,
+    {
+        if self.shift_bind != 0 {
+            Err(sqlx::Error::InvalidArgument(
+                "build_sqlx can't bind the placeholders shifted by shift_bind".into(),
+            ))?
+        }
+
+        self.dialect = Box::new(DB::DATABASE);
+
+        let (sql, values) = self
+            .build()
+            .map_err(|err| sqlx::Error::InvalidArgument(err.to_string()))?;
 
-            if let Ok(offset) = self.url_query.check_offset() {
-                append_offset(&mut self.sql, offset);
+        let mut args = DB::Arguments::default();
+        for value in values {
+            DB::bind_value(&mut args, value).map_err(sqlx::Error::Encode)?;
+        }
+
+        Ok(sqlx::QueryBuilder::with_arguments(sql, args))
+    }
+
+    fn dialect(&self) -> Quoting<'_> {
+        Quoting {
+            dialect: self.dialect.as_ref(),
+            quote_identifiers: self.quote_identifiers,
+        }
+    }
+
+    // Returns the quoted column for a field. The schema's column is used as is, otherwise the field
+    // is converted to `case`.
+    fn column(&self, field: &str, case: Option<Case>, dialect: &dyn Dialect) -> String {
+        let spec = self.schema.and_then(|schema| schema.get(field));
+        let table = spec
+            .and_then(|spec| spec.table.as_deref())
+            .or_else(|| self.map_columns.get(field).copied());
+
+        match spec.and_then(|spec| spec.column.as_deref()) {
+            Some(column) => dialect::column(table.as_ref(), column, None, dialect),
+            None => dialect::column(table.as_ref(), field, case, dialect),
+        }
+    }
+
+    // Sorts are converted to snake case unless another case is set
+    fn sort_column(&self, field: &str, dialect: &dyn Dialect) -> String {
+        self.column(
+            field,
+            Some(self.convert_case.unwrap_or(Case::Snake)),
+            dialect,
+        )
+    }
+
+    // Returns the sorts of the url query, or the default sort, followed by the tiebreaker
+    fn sorts(&self) -> Vec<Sort> {
+        let mut sorts = if self.url_query.sort.is_empty() {
+            self.default_sort.iter().cloned().collect()
+        } else {
+            self.url_query.sort.clone()
+        };
+
+        if let Some(tiebreaker) = self.tiebreaker {
+            if !sorts.iter().any(|sort| sort.field == tiebreaker) {
+                // The tiebreaker follows the direction of the last sort
+                let sort_by = match sorts.last() {
+                    Some(sort) => sort.sort_by.clone(),
+                    None => SortBy::ASC,
+                };
+
+                sorts.push(Sort {
+                    field: tiebreaker.into(),
+                    sort_by,
+                    nulls: None,
+                });
+            }
+        }
+
+        sorts
+    }
+
+    // Returns the cursor of the url query if it has a value for every sort, an invalid cursor is
+    // rejected by check_cursor
+    fn cursor(&self) -> Option<&Cursor> {
+        let cursor = self.url_query.cursor.as_ref()?;
+        let sorts = self.sorts();
+
+        if sorts.is_empty() || sorts.len() != cursor.values.len() {
+            return None;
+        }
+
+        Some(cursor)
+    }
+
+    // Returns the condition for the rows after or before the cursor, eg
+    // `(created_at, id) < ($1, $2)`, pushing its values onto `args`. Without row values, or with
+    // sorts in different directions, it's expanded into
+    // `created_at < $1 OR (created_at = $2 AND id < $3)`.
+    fn keyset(
+        &self,
+        cursor: &Cursor,
+        idx: usize,
+        dialect: &dyn Dialect,
+        args: &mut Vec<Value>,
+    ) -> String {
+        let sorts = self.sorts();
+
+        let columns: Vec<String> = sorts
+            .iter()
+            .map(|sort| self.sort_column(&sort.field, dialect))
+            .collect();
+        let values: Vec<Value> = sorts
+            .iter()
+            .zip(&cursor.values)
+            .map(|(sort, value)| Value::parse_or_string(self.field_type(&sort.field), value))
+            .collect();
+        let operators: Vec<&str> = sorts
+            .iter()
+            .map(|sort| match (cursor.direction, &sort.sort_by) {
+                (Direction::After, SortBy::ASC) | (Direction::Before, SortBy::DESC) => ">",
+                (Direction::After, SortBy::DESC) | (Direction::Before, SortBy::ASC) => "<",
+            })
+            .collect();
+
+        let mut sql = String::new();
+        let mut idx = idx;
+
+        // (created_at, id) < ($1, $2)
+        if columns.len() > 1
+            && dialect.supports_row_values()
+            && operators.iter().all(|op| *op == operators[0])
+        {
+            sql.push('(');
+            sql.push_str(&columns.join(", "));
+            sql.push_str(") ");
+            sql.push_str(operators[0]);
+            sql.push_str(" (");
+            for i in 0..values.len() {
+                if i > 0 {
+                    sql.push_str(", ");
+                }
+                dialect.push_placeholder(&mut sql, idx);
+                idx += 1;
+            }
+            sql.push(')');
+            args.extend(values);
+
+            return sql;
+        }
+
+        // created_at < $1 OR (created_at = $2 AND id < $3)
+        if columns.len() > 1 {
+            sql.push('(');
+        }
+        for i in 0..columns.len() {
+            if i > 0 {
+                sql.push_str(" OR (");
             }
+            for j in 0..=i {
+                if j > 0 {
+                    sql.push_str(" AND ");
+                }
+                sql.push_str(&columns[j]);
+                sql.push(' ');
+                sql.push_str(if j == i { operators[j] } else { "=" });
+                sql.push(' ');
+                dialect.push_placeholder(&mut sql, idx);
+                idx += 1;
+                args.push(values[j].clone());
+            }
+            if i > 0 {
+                sql.push(')');
+            }
+        }
+        if columns.len() > 1 {
+            sql.push(')');
+        }
+
+        sql
+    }
+
+    fn field_type(&self, field: &str) -> Option<&'a FieldType> {
+        self.schema
+            .and_then(|schema| schema.get(field))
+            .map(|spec| &spec.field_type)
+    }
+
+    fn append_limit_offset(&mut self) {
+        let limit = self.limit();
+        let offset = self.offset();
+
+        self.dialect.push_pagination(&mut self.sql, limit, offset);
+    }
+
+    fn limit(&self) -> Option<u64> {
+        let limit = self
+            .url_query
+            .limit_offset
+            .0
+            .or(self.default_limit)
+            .or(self.max_limit)?;
+
+        match self.max_limit {
+            Some(max) => Some(limit.min(max)),
+            None => Some(limit),
         }
+    }
 
-        (self.sql, args)
+    fn offset(&self) -> Option<u64> {
+        // A cursor is used instead of the offset
+        match self.cursor() {
+            Some(_) => None,
+            None => self.url_query.limit_offset.1,
+        }
     }
 }
 
-fn gen_sql_select(table: &str, columns: Vec<&str>) -> String {
-    let mut sql = String::from("SELECT ");
-    let columns = columns.join(", ");
-    sql.push_str(&columns);
-    sql.push_str(" FROM ");
-    sql.push_str(table);
-    sql
+// The builder's dialect with identifier quoting turned off unless it's enabled
+struct Quoting<'a> {
+    dialect: &'a dyn Dialect,
+    quote_identifiers: bool,
 }
 
-fn append_limit(sql: &mut String, limit: &str) {
-    sql.push_str(" LIMIT ");
-    sql.push_str(limit);
+impl Dialect for Quoting<'_> {
+    fn push_placeholder(&self, sql: &mut String, idx: usize) {
+        self.dialect.push_placeholder(sql, idx)
+    }
+
+    fn quote_identifier(&self, ident: &str) -> String {
+        if self.quote_identifiers {
+            self.dialect.quote_identifier(ident)
+        } else {
+            ident.to_owned()
+        }
+    }
+
+    fn push_like(
+        &self,
+        sql: &mut String,
+        column: &str,
+        idx: usize,
+        case_insensitive: bool,
+        escape: Option<char>,
+    ) {
+        self.dialect
+            .push_like(sql, column, idx, case_insensitive, escape)
+    }
+
+    fn push_sort(&self, sql: &mut String, column: &str, sort_by: &SortBy, nulls: Option<&Nulls>) {
+        self.dialect.push_sort(sql, column, sort_by, nulls)
+    }
+
+    fn push_pagination(&self, sql: &mut String, limit: Option<u64>, offset: Option<u64>) {
+        self.dialect.push_pagination(sql, limit, offset)
+    }
+
+    fn boolean_literal(&self, value: bool) -> &'static str {
+        self.dialect.boolean_literal(value)
+    }
+
+    fn supports_array_binds(&self) -> bool {
+        self.dialect.supports_array_binds()
+    }
+
+    fn supports_row_values(&self) -> bool {
+        self.dialect.supports_row_values()
+    }
+
+    fn requires_sort_to_paginate(&self) -> bool {
+        self.dialect.requires_sort_to_paginate()
+    }
 }
 
-fn append_offset(sql: &mut String, offset: &str) {
-    sql.push_str(" OFFSET ");
-    sql.push_str(offset);
+/// An error building a query with a [`QueryBuilder`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum BuildError {
+    /// The url query can't be used, eg its cursor doesn't match the sorts.
+    Query(ParseError),
+    /// The query is paginated without a sort on a database that can only paginate a sorted query,
+    /// set a [default sort](QueryBuilder::default_sort) or [tiebreaker](QueryBuilder::tiebreaker).
+    SortRequired,
 }
 
-/// Bind args to an sqlx query with the required types.
-///
-/// ```ignore
-/// pub async fn get_orders(
-///     pool: &PgPool,
-///     query: UrlQuery,
-/// ) -> Result<Vec<Order>, Either<sqlx::Error, ParseError>> {
-///     let (sql, args) = QueryBuilder::from_str(
-///         "SELECT * FROM orders",
-///         query,
-///     )
-///     .build();
-///
-///     let mut query = sqlx::query_as(&sql);
-///
-///     bind!(
-///         args => query,
-///         error: Either::Right(ParseError),
-///         "id" => Uuid,
-///         "userId" => i64
-///     );
-///
-///     Ok(query.fetch_all(pool).await.map_err(|e| Either::Left(e))?)
-/// }
-/// ```
-#[macro_export]
-macro_rules! sqlx_bind {
-    ( $args:ident => $query:ident, error: $error:expr, $( $x:expr => $t:ty ),* ) => {
-        {
-            for (column, arg) in $args {
-                match column.as_str() {
-                    $(
-                        $x => {
-                            let parsed: $t = arg.parse().map_err(|_| {
-                                $error
-                            })?;
-                            $query = $query.bind(parsed);
-                        }
-                    )*
-                    _ => {}
-                }
-            }
+impl From<ParseError> for BuildError {
+    fn from(err: ParseError) -> Self {
+        BuildError::Query(err)
+    }
+}
+
+impl std::fmt::Display for BuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::Query(err) => write!(f, "{}", err),
+            BuildError::SortRequired => write!(
+                f,
+                "a default sort or tiebreaker is required to paginate on this database"
+            ),
         }
-    };
+    }
+}
+
+impl std::error::Error for BuildError {}
+
+fn gen_sql_select(table: &str, columns: Vec<&str>) -> String {
+    let mut sql = String::from("SELECT ");
+    let columns = columns.join(", ");
+    sql.push_str(&columns);
+    sql.push_str(" FROM ");
+    sql.push_str(table);
+    sql
 }
 
 #[cfg(test)]
@@ -266,73 +789,466 @@ mod test {
 
     use convert_case::Case;
 
-    use crate::{sql::Database, UrlQuery};
+    use crate::{
+        cursor::Cursor,
+        schema::{FieldSpec, FieldType, Schema},
+        sort::Sort,
+        sql::Database,
+        value::Value,
+        ErrorKind, ParseError, UrlQuery,
+    };
+
+    use super::{BuildError, QueryBuilder};
 
-    use super::QueryBuilder;
+    const DATABASES: [Database; 2] = [Database::Postgres, Database::Sqlite];
+
+    fn query_error<T: std::fmt::Debug>(result: Result<T, BuildError>) -> ParseError {
+        match result.unwrap_err() {
+            BuildError::Query(err) => err,
+            err => panic!("expected a query error, got {:?}", err),
+        }
+    }
+
+    // Expected SQL is written for Postgres, SQLite only differs in its placeholders.
+    fn placeholders(sql: &str, database: Database) -> String {
+        match database {
+            Database::Sqlite => sql.replace('$', "?"),
+            _ => sql.to_owned(),
+        }
+    }
 
     #[test]
     fn test_query_builder_from_str() {
-        let query =
-            "userId=123&userName=bob&filter[]=orderId-eq-1&filter[]=price-ge-200&sort=price-desc&limit=10&offset=0";
+        for database in DATABASES {
+            let query =
+                "userId=123&userName=bob&filter[]=orderId-eq-1&filter[]=price-ge-200&sort=price-desc&limit=10&offset=0";
+
+            let parsed = UrlQuery::new(query, ["userId", "userName", "orderId", "price"]).unwrap();
+
+            let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+                .convert_case(Case::Snake)
+                .set_database(database)
+                .build()
+                .unwrap();
+
+            let expected = "SELECT * FROM orders \
+            WHERE \"user_id\" = $1 AND \"user_name\" = $2 \
+            AND \"order_id\" = $3 AND \"price\" >= $4 \
+            ORDER BY \"price\" DESC \
+            LIMIT 10 \
+            OFFSET 0";
+
+            assert_eq!(sql, placeholders(expected, database));
+            assert_eq!(args.len(), 4);
+        }
+    }
 
-        let parsed = UrlQuery::new(query, ["userId", "userName", "orderId", "price"]).unwrap();
+    #[test]
+    fn test_query_builder_new() {
+        for database in DATABASES {
+            let query =
+                "userId=123&userName=bob&filter[]=orderId-eq-1&filter[]=price-ge-200&sort=price-desc&limit=10&offset=0";
+
+            let parsed = UrlQuery::new(query, ["userId", "userName", "orderId", "price"]).unwrap();
+
+            let (sql, args) = QueryBuilder::new("orders", vec!["id", "status"], parsed)
+                .convert_case(Case::Snake)
+                .set_database(database)
+                .build()
+                .unwrap();
+
+            let expected = "SELECT id, status FROM orders \
+            WHERE \"user_id\" = $1 AND \"user_name\" = $2 \
+            AND \"order_id\" = $3 AND \"price\" >= $4 \
+            ORDER BY \"price\" DESC \
+            LIMIT 10 \
+            OFFSET 0";
+
+            assert_eq!(sql, placeholders(expected, database));
+            assert_eq!(args.len(), 4);
+        }
+    }
 
-        let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+    #[test]
+    fn test_query_builder_new_append_joins() {
+        for database in DATABASES {
+            let query =
+                "userId=123&userName=bob&filter[]=orderId-eq-1&filter[]=price-ge-200&sort=price-desc&limit=10&offset=0";
+
+            let parsed = UrlQuery::new(query, ["userId", "userName", "orderId", "price"]).unwrap();
+
+            let (sql, args) = QueryBuilder::new("orders", vec!["id", "status"], parsed)
+                .append("JOIN users ON users.id = order.user_id")
+                .append("JOIN inventory ON inventory.id = order.inventory_id")
+                .convert_case(Case::Snake)
+                .set_database(database)
+                .build()
+                .unwrap();
+
+            let expected = "SELECT id, status FROM orders \
+            JOIN users ON users.id = order.user_id \
+            JOIN inventory ON inventory.id = order.inventory_id \
+            WHERE \"user_id\" = $1 AND \"user_name\" = $2 \
+            AND \"order_id\" = $3 AND \"price\" >= $4 \
+            ORDER BY \"price\" DESC \
+            LIMIT 10 \
+            OFFSET 0";
+
+            assert_eq!(sql, placeholders(expected, database));
+            assert_eq!(args.len(), 4);
+        }
+    }
+
+    #[test]
+    fn test_query_builder_new_map_columns() {
+        for database in DATABASES {
+            let query = "id=1&group=id&sort=createdAt-desc";
+
+            let parsed = UrlQuery::new(query, ["id", "createdAt"]).unwrap();
+
+            let (sql, args) = QueryBuilder::from_str(
+                "SELECT orders.id, user_id, status, address_id, orders.created_at FROM orders",
+                parsed,
+            )
+            .append("JOIN order_items ON orders.id = order_items.order_id")
+            .append("JOIN inventory ON order_items.inventory_id = inventory.id")
+            .map_columns(HashMap::from([("id", "orders"), ("createdAt", "orders")]))
             .convert_case(Case::Snake)
-            .build();
+            .set_database(database)
+            .build()
+            .unwrap();
+
+            let expected =
+                "SELECT orders.id, user_id, status, address_id, orders.created_at FROM orders \
+                 JOIN order_items ON orders.id = order_items.order_id \
+                 JOIN inventory ON order_items.inventory_id = inventory.id \
+                 WHERE \"orders\".\"id\" = $1 GROUP BY \"orders\".\"id\" ORDER BY \"orders\".\"created_at\" DESC";
+
+            assert_eq!(sql, placeholders(expected, database));
+            assert_eq!(args.len(), 1);
+        }
+    }
 
-        let expected = "SELECT * FROM orders \
-        WHERE user_id = $1 AND user_name = $2 \
-        AND order_id = $3 AND price >= $4 \
-        ORDER BY price DESC \
-        LIMIT 10 \
-        OFFSET 0";
+    #[test]
+    fn test_query_builder_multiple_sorts() {
+        for database in DATABASES {
+            let query = "sort=price-desc,createdAt-asc&sort=id-asc";
 
-        assert_eq!(sql, expected);
-        assert_eq!(args.len(), 4);
+            let parsed = UrlQuery::new(query, ["price", "createdAt", "id"]).unwrap();
+
+            let (sql, _) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+                .map_columns(HashMap::from([("id", "orders"), ("createdAt", "orders")]))
+                .convert_case(Case::Snake)
+                .set_database(database)
+                .build()
+                .unwrap();
+
+            assert_eq!(sql, placeholders("SELECT * FROM orders ORDER BY \"price\" DESC, \"orders\".\"created_at\" ASC, \"orders\".\"id\" ASC", database));
+        }
     }
 
     #[test]
-    fn test_query_builder_new() {
-        let query =
-            "userId=123&userName=bob&filter[]=orderId-eq-1&filter[]=price-ge-200&sort=price-desc&limit=10&offset=0";
+    fn test_query_builder_group_having() {
+        for database in DATABASES {
+            let query = "filter[]=status-ne-cancelled&group=userId,status&having[]=count-gt-5&having[]=sum(totalPrice)-ge-100";
+
+            let parsed = UrlQuery::new(query, ["userId", "status", "totalPrice"]).unwrap();
+
+            let (sql, args) = QueryBuilder::from_str("SELECT user_id, status FROM orders", parsed)
+                .map_columns(HashMap::from([
+                    ("userId", "orders"),
+                    ("totalPrice", "orders"),
+                ]))
+                .convert_case(Case::Snake)
+                .set_database(database)
+                .build()
+                .unwrap();
+
+            let expected = "SELECT user_id, status FROM orders \
+            WHERE \"status\" != $1 \
+            GROUP BY \"orders\".\"user_id\", \"status\" \
+            HAVING COUNT(*) > $2 AND SUM(\"orders\".\"total_price\") >= $3";
+
+            assert_eq!(sql, placeholders(expected, database));
+            assert_eq!(args.len(), 3);
+        }
+    }
 
-        let parsed = UrlQuery::new(query, ["userId", "userName", "orderId", "price"]).unwrap();
+    #[test]
+    fn test_query_builder_schema() {
+        let schema = Schema::new()
+            .field(FieldSpec::new("id", FieldType::Int).table("orders"))
+            .field(
+                FieldSpec::new("price", FieldType::Float)
+                    .column("unit_price")
+                    .table("items")
+                    .sortable(true),
+            )
+            .field(FieldSpec::new("createdAt", FieldType::Timestamp).sortable(true));
+
+        for database in DATABASES {
+            let query = "id=1&q=or(price-gt-10;price-lt-5)&sort=price-desc,createdAt-asc";
+
+            let parsed = UrlQuery::with_schema(query, &schema).unwrap();
+
+            let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+                .schema(&schema)
+                .set_database(database)
+                .build()
+                .unwrap();
+
+            let expected = "SELECT * FROM orders \
+            WHERE \"orders\".\"id\" = $1 \
+            AND (\"items\".\"unit_price\" > $2 OR \"items\".\"unit_price\" < $3) \
+            ORDER BY \"items\".\"unit_price\" DESC, \"created_at\" ASC";
+
+            assert_eq!(sql, placeholders(expected, database));
+            assert_eq!(args, [Value::I64(1), Value::F64(10.0), Value::F64(5.0)]);
+        }
+    }
 
-        let (sql, args) = QueryBuilder::new("orders", vec!["id", "status"], parsed)
+    #[test]
+    fn test_query_builder_quote_identifiers() {
+        let query = "filter[]=order-eq-1&group=group&sort=select-desc";
+
+        let parsed = UrlQuery::new(query, ["order", "group", "select"]).unwrap();
+        let (sql, _) = QueryBuilder::from_str("SELECT * FROM items", parsed)
+            .map_columns(HashMap::from([("order", "items")]))
+            .set_database(Database::MySQL)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM items WHERE `items`.`order` = ? GROUP BY `group` ORDER BY `select` DESC"
+        );
+
+        let parsed = UrlQuery::new(query, ["order", "group", "select"]).unwrap();
+        let (sql, _) = QueryBuilder::from_str("SELECT * FROM items", parsed)
+            .map_columns(HashMap::from([("order", "items")]))
+            .quote_identifiers(false)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM items WHERE items.order = $1 GROUP BY group ORDER BY select DESC"
+        );
+    }
+
+    #[test]
+    fn test_append_where() {
+        for database in DATABASES {
+            let query = "filter[]=userId-eq-1&filter[]=id-eq-2";
+
+            let parsed = UrlQuery::new(query, ["userId", "id"]).unwrap();
+
+            let mut builder = QueryBuilder::from_str("", parsed).set_database(database);
+
+            let mut args = builder.append_where().into_iter();
+
+            let user_id = args.next().unwrap();
+            assert_eq!(user_id, Value::from("1"));
+
+            let id = args.next().unwrap();
+            assert_eq!(id, Value::from("2"));
+        }
+    }
+
+    #[test]
+    fn test_shift_bind() {
+        for database in DATABASES {
+            let query = "filter[]=userId-eq-1&filter[]=id-eq-2";
+
+            let parsed = UrlQuery::new(query, ["userId", "id"]).unwrap();
+
+            let (sql, args) = QueryBuilder::from_str(
+                &placeholders(
+                    "SELECT id, (SELECT postcode FROM address WHERE id = $1) FROM orders",
+                    database,
+                ),
+                parsed,
+            )
+            .shift_bind(1)
             .convert_case(Case::Snake)
-            .build();
+            .set_database(database)
+            .build()
+            .unwrap();
 
-        let expected = "SELECT id, status FROM orders \
-        WHERE user_id = $1 AND user_name = $2 \
-        AND order_id = $3 AND price >= $4 \
-        ORDER BY price DESC \
-        LIMIT 10 \
-        OFFSET 0";
+            let expected = "SELECT id, (SELECT postcode FROM address WHERE id = $1) FROM orders WHERE \"user_id\" = $2 AND \"id\" = $3";
 
-        assert_eq!(sql, expected);
-        assert_eq!(args.len(), 4);
+            assert_eq!(sql, placeholders(expected, database));
+            assert_eq!(args.len(), 2);
+        }
     }
 
     #[test]
-    fn test_query_builder_new_append_joins() {
+    fn test_query_builder_default_and_max_limit() {
+        for database in DATABASES {
+            let parsed = UrlQuery::new("offset=20", []).unwrap();
+            let (sql, _) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+                .default_limit(25)
+                .max_limit(100)
+                .set_database(database)
+                .build()
+                .unwrap();
+            assert_eq!(
+                sql,
+                placeholders("SELECT * FROM orders LIMIT 25 OFFSET 20", database)
+            );
+
+            let parsed = UrlQuery::new("limit=1000", []).unwrap();
+            let (sql, _) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+                .default_limit(25)
+                .max_limit(100)
+                .set_database(database)
+                .build()
+                .unwrap();
+            assert_eq!(
+                sql,
+                placeholders("SELECT * FROM orders LIMIT 100", database)
+            );
+
+            let parsed = UrlQuery::new("", []).unwrap();
+            let (sql, _) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+                .max_limit(100)
+                .set_database(database)
+                .build()
+                .unwrap();
+            assert_eq!(
+                sql,
+                placeholders("SELECT * FROM orders LIMIT 100", database)
+            );
+        }
+    }
+
+    #[test]
+    fn test_query_builder_null() {
+        for database in DATABASES {
+            let query = "filter[]=userId-eq-1&filter[]=deletedAt-null&filter[]=price-ge-200";
+
+            let parsed = UrlQuery::new(query, ["userId", "deletedAt", "price"]).unwrap();
+            let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+                .convert_case(Case::Snake)
+                .set_database(database)
+                .build()
+                .unwrap();
+
+            assert_eq!(sql, placeholders("SELECT * FROM orders WHERE \"user_id\" = $1 AND \"deleted_at\" IS NULL AND \"price\" >= $2", database));
+            assert_eq!(args.len(), 2);
+        }
+    }
+
+    #[test]
+    fn test_query_builder_between() {
+        for database in DATABASES {
+            let query = "filter[]=createdAt-between-2024-01-01,2024-02-01&filter[]=price-ge-200";
+
+            let parsed = UrlQuery::new(query, ["createdAt", "price"]).unwrap();
+            let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+                .convert_case(Case::Snake)
+                .set_database(database)
+                .build()
+                .unwrap();
+
+            assert_eq!(
+                sql,
+                placeholders(
+                    "SELECT * FROM orders WHERE \"created_at\" BETWEEN $1 AND $2 AND \"price\" >= $3",
+                    database
+                )
+            );
+            assert_eq!(
+                args,
+                vec![
+                    Value::from("2024-01-01"),
+                    Value::from("2024-02-01"),
+                    Value::from("200"),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_query_builder_exprs() {
+        for database in DATABASES {
+            let query = "filter[]=userId-eq-1&q=or(status-eq-a;and(status-eq-b;or(price-gt-10;featured-eq-true)))&q=not(deletedAt-notnull)&limit=10";
+
+            let parsed = UrlQuery::new(
+                query,
+                ["userId", "status", "price", "featured", "deletedAt"],
+            )
+            .unwrap();
+            let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+                .convert_case(Case::Snake)
+                .set_database(database)
+                .build()
+                .unwrap();
+
+            let expected = "SELECT * FROM orders \
+            WHERE \"user_id\" = $1 \
+            AND (\"status\" = $2 OR (\"status\" = $3 AND (\"price\" > $4 OR \"featured\" = $5))) \
+            AND NOT (\"deleted_at\" IS NOT NULL) \
+            LIMIT 10";
+
+            assert_eq!(sql, placeholders(expected, database));
+            assert_eq!(args.len(), 5);
+            assert_eq!(args[3], Value::from("10"));
+        }
+    }
+
+    #[test]
+    fn test_query_builder_in() {
+        let query = "filter[]=status-in-pending,shipped&filter[]=price-ge-200";
+
+        for database in DATABASES {
+            let parsed = UrlQuery::new(query, ["status", "price"]).unwrap();
+            let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+                .set_database(database)
+                .build()
+                .unwrap();
+
+            assert_eq!(
+                sql,
+                placeholders(
+                    "SELECT * FROM orders WHERE \"status\" IN ($1, $2) AND \"price\" >= $3",
+                    database
+                )
+            );
+            assert_eq!(args.len(), 3);
+        }
+
+        // Array binds are only used on Postgres
+        let parsed = UrlQuery::new(query, ["status", "price"]).unwrap();
+        let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+            .array_binds(true)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM orders WHERE \"status\" = ANY($1) AND \"price\" >= $2"
+        );
+        assert_eq!(args.len(), 2);
+    }
+
+    #[test]
+    fn test_query_builder_set_database_mysql() {
         let query =
             "userId=123&userName=bob&filter[]=orderId-eq-1&filter[]=price-ge-200&sort=price-desc&limit=10&offset=0";
 
         let parsed = UrlQuery::new(query, ["userId", "userName", "orderId", "price"]).unwrap();
 
         let (sql, args) = QueryBuilder::new("orders", vec!["id", "status"], parsed)
-            .append("JOIN users ON users.id = order.user_id")
-            .append("JOIN inventory ON inventory.id = order.inventory_id")
             .convert_case(Case::Snake)
-            .build();
+            .set_database(Database::MySQL)
+            .build()
+            .unwrap();
 
         let expected = "SELECT id, status FROM orders \
-        JOIN users ON users.id = order.user_id \
-        JOIN inventory ON inventory.id = order.inventory_id \
-        WHERE user_id = $1 AND user_name = $2 \
-        AND order_id = $3 AND price >= $4 \
-        ORDER BY price DESC \
+        WHERE `user_id` = ? AND `user_name` = ? \
+        AND `order_id` = ? AND `price` >= ? \
+        ORDER BY `price` DESC \
         LIMIT 10 \
         OFFSET 0";
 
@@ -341,88 +1257,252 @@ mod test {
     }
 
     #[test]
-    fn test_query_builder_new_map_columns() {
-        let query = "id=1&group=id&sort=createdAt-desc";
+    fn test_query_builder_set_database_sqlite() {
+        let query =
+            "filter[]=name-ilike-bob%25&filter[]=status-in-a,b&sort=shippedAt-desc-nullslast";
 
-        let parsed = UrlQuery::new(query, ["id", "createdAt"]).unwrap();
+        let parsed = UrlQuery::new(query, ["name", "status", "shippedAt"]).unwrap();
 
-        let (sql, args) = QueryBuilder::from_str(
-            "SELECT orders.id, user_id, status, address_id, orders.created_at FROM orders",
-            parsed,
-        )
-        .append("JOIN order_items ON orders.id = order_items.order_id")
-        .append("JOIN inventory ON order_items.inventory_id = inventory.id")
-        .map_columns(HashMap::from([("id", "orders"), ("createdAt", "orders")]))
-        .convert_case(Case::Snake)
-        .build();
-
-        let expected =
-            "SELECT orders.id, user_id, status, address_id, orders.created_at FROM orders \
-             JOIN order_items ON orders.id = order_items.order_id \
-             JOIN inventory ON order_items.inventory_id = inventory.id \
-             WHERE orders.id = $1 GROUP BY orders.id ORDER BY orders.created_at DESC";
+        let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+            .convert_case(Case::Snake)
+            .array_binds(true)
+            .set_database(Database::Sqlite)
+            .build()
+            .unwrap();
+
+        let expected = "SELECT * FROM orders \
+        WHERE \"name\" LIKE ?1 AND \"status\" IN (?2, ?3) \
+        ORDER BY \"shipped_at\" DESC NULLS LAST";
 
         assert_eq!(sql, expected);
-        assert_eq!(args.len(), 1);
+        assert_eq!(args.len(), 3);
     }
 
     #[test]
-    fn test_append_where() {
-        let query = "filter[]=userId-eq-1&filter[]=id-eq-2";
+    fn test_query_builder_set_database_mssql() {
+        let query =
+            "userId=123&filter[]=name-ilike-bob%25&filter[]=status-in-a,b&sort=shippedAt-desc-nullslast&limit=10&offset=20";
 
-        let parsed = UrlQuery::new(query, ["userId", "id"]).unwrap();
+        let parsed = UrlQuery::new(query, ["userId", "name", "status", "shippedAt"]).unwrap();
 
-        let mut builder = QueryBuilder::from_str("", parsed);
+        let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+            .convert_case(Case::Snake)
+            .set_database(Database::MsSql)
+            .build()
+            .unwrap();
 
-        let mut args = builder.append_where().into_iter();
+        let expected = "SELECT * FROM orders \
+        WHERE [user_id] = @p1 AND LOWER([name]) LIKE LOWER(@p2) AND [status] IN (@p3, @p4) \
+        ORDER BY CASE WHEN [shipped_at] IS NULL THEN 1 ELSE 0 END ASC, [shipped_at] DESC \
+        OFFSET 20 ROWS FETCH NEXT 10 ROWS ONLY";
 
-        let user_id = args.next().unwrap().1;
-        assert_eq!(user_id, "1");
+        assert_eq!(sql, expected);
+        assert_eq!(args.len(), 4);
+    }
+
+    #[test]
+    fn test_query_builder_mssql_pagination_sort() {
+        // SQL Server can't paginate without an ORDER BY, and there's nothing to sort by
+        let parsed = UrlQuery::new("limit=10", []).unwrap();
+        let err = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+            .set_database(Database::MsSql)
+            .build()
+            .unwrap_err();
+
+        assert_eq!(err, BuildError::SortRequired);
+
+        // The default sort or the tiebreaker is used instead
+        let parsed = UrlQuery::new("offset=10", []).unwrap();
+        let (sql, _) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+            .set_database(Database::MsSql)
+            .default_sort(Sort::new("id-asc").unwrap())
+            .build()
+            .unwrap();
+
+        assert_eq!(sql, "SELECT * FROM orders ORDER BY [id] ASC OFFSET 10 ROWS");
+
+        let parsed = UrlQuery::new("limit=10", []).unwrap();
+        let (sql, _) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+            .set_database(Database::MsSql)
+            .tiebreaker("id")
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT * FROM orders ORDER BY [id] ASC OFFSET 0 ROWS FETCH NEXT 10 ROWS ONLY"
+        );
 
-        let id = args.next().unwrap().1;
-        assert_eq!(id, "2");
+        let parsed = UrlQuery::new("", []).unwrap();
+        let (sql, _) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+            .set_database(Database::MsSql)
+            .build()
+            .unwrap();
+
+        assert_eq!(sql, "SELECT * FROM orders");
     }
 
     #[test]
-    fn test_shift_bind() {
-        let query = "filter[]=userId-eq-1&filter[]=id-eq-2";
+    fn test_query_builder_offset_without_limit() {
+        let expected = [
+            (Database::Postgres, "SELECT * FROM orders OFFSET 20"),
+            (
+                Database::MySQL,
+                "SELECT * FROM orders LIMIT 18446744073709551615 OFFSET 20",
+            ),
+            (Database::Sqlite, "SELECT * FROM orders LIMIT -1 OFFSET 20"),
+        ];
+
+        for (database, expected) in expected {
+            let parsed = UrlQuery::new("offset=20", []).unwrap();
+            let (sql, _) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+                .set_database(database)
+                .build()
+                .unwrap();
+
+            assert_eq!(sql, expected);
+        }
+    }
 
-        let parsed = UrlQuery::new(query, ["userId", "id"]).unwrap();
+    #[test]
+    fn test_query_builder_cursor() {
+        let schema = Schema::new()
+            .field(FieldSpec::new("createdAt", FieldType::Timestamp).sortable(true))
+            .field(FieldSpec::new("id", FieldType::Int));
+
+        let cursor = Cursor::after(["2024-01-31T12:00:00Z", "12"]).encode();
+        let query = format!(
+            "status=paid&sort=createdAt-desc&cursor={}&limit=10&offset=30",
+            cursor
+        );
+
+        let expected = [
+            (
+                Database::Postgres,
+                "SELECT * FROM orders WHERE \"status\" = $1 \
+                 AND (\"created_at\", \"id\") < ($2, $3) \
+                 ORDER BY \"created_at\" DESC, \"id\" DESC LIMIT 10",
+            ),
+            (
+                Database::MySQL,
+                "SELECT * FROM orders WHERE `status` = ? \
+                 AND (`created_at` < ? OR (`created_at` = ? AND `id` < ?)) \
+                 ORDER BY `created_at` DESC, `id` DESC LIMIT 10",
+            ),
+        ];
+
+        for (database, expected) in expected {
+            let parsed = UrlQuery::new(&query, ["status", "createdAt"]).unwrap();
+            let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+                .schema(&schema)
+                .tiebreaker("id")
+                .set_database(database)
+                .build()
+                .unwrap();
+
+            assert_eq!(sql, expected);
+            assert_eq!(args[0], Value::String("paid".into()));
+            assert_eq!(args.last(), Some(&Value::I64(12)));
+        }
+    }
 
-        let (sql, args) = QueryBuilder::from_str(
-            "SELECT id, (SELECT postcode FROM address WHERE id = $1) FROM orders",
-            parsed,
-        )
-        .shift_bind(1)
-        .convert_case(Case::Snake)
-        .build();
+    #[test]
+    fn test_query_builder_cursor_before() {
+        let cursor = Cursor::before(["10", "5"]).encode();
+        let query = format!("sort=price-asc&cursor={}&limit=10", cursor);
 
-        let expected = "SELECT id, (SELECT postcode FROM address WHERE id = $1) FROM orders WHERE user_id = $2 AND id = $3";
+        let parsed = UrlQuery::new(&query, ["price"]).unwrap();
+        let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+            .tiebreaker("id")
+            .set_database(Database::Sqlite)
+            .build()
+            .unwrap();
 
-        assert_eq!(sql, expected);
+        assert_eq!(
+            sql,
+            "SELECT * FROM orders WHERE (\"price\", \"id\") < (?1, ?2) \
+             ORDER BY \"price\" DESC, \"id\" DESC LIMIT 10"
+        );
         assert_eq!(args.len(), 2);
     }
 
     #[test]
-    fn test_query_builder_set_database_mysql() {
-        let query =
-            "userId=123&userName=bob&filter[]=orderId-eq-1&filter[]=price-ge-200&sort=price-desc&limit=10&offset=0";
+    fn test_query_builder_cursor_mixed_directions() {
+        let cursor = Cursor::after(["a", "10", "5"]).encode();
+        let query = format!("sort=status-asc,price-desc&cursor={}", cursor);
 
-        let parsed = UrlQuery::new(query, ["userId", "userName", "orderId", "price"]).unwrap();
-
-        let (sql, args) = QueryBuilder::new("orders", vec!["id", "status"], parsed)
-            .convert_case(Case::Snake)
-            .set_database(Database::MySQL)
-            .build();
+        let parsed = UrlQuery::new(&query, ["status", "price"]).unwrap();
+        let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+            .tiebreaker("id")
+            .build()
+            .unwrap();
 
-        let expected = "SELECT id, status FROM orders \
-        WHERE user_id = ? AND user_name = ? \
-        AND order_id = ? AND price >= ? \
-        ORDER BY price DESC \
-        LIMIT 10 \
-        OFFSET 0";
+        assert_eq!(
+            sql,
+            "SELECT * FROM orders WHERE (\"status\" > $1 \
+             OR (\"status\" = $2 AND \"price\" < $3) \
+             OR (\"status\" = $4 AND \"price\" = $5 AND \"id\" < $6)) \
+             ORDER BY \"status\" ASC, \"price\" DESC, \"id\" DESC"
+        );
+        assert_eq!(args.len(), 6);
+    }
 
-        assert_eq!(sql, expected);
-        assert_eq!(args.len(), 4);
+    #[test]
+    fn test_query_builder_cursor_mismatch() {
+        // A cursor from a page that was sorted by another number of fields
+        let cursor = Cursor::after(["12"]).encode();
+        let query = format!("sort=price-asc&cursor={}&offset=10", cursor);
+
+        let parsed = UrlQuery::new(&query, ["price"]).unwrap();
+        let builder = QueryBuilder::from_str("SELECT * FROM orders", parsed).tiebreaker("id");
+        assert_eq!(builder.cursor_fields(), ["price", "id"]);
+
+        let err = query_error(builder.build());
+        assert_eq!(err.kind, ErrorKind::InvalidCursor);
+        assert_eq!(err.expected, ["price", "id"]);
+
+        // Without a sort or a tiebreaker
+        let parsed = UrlQuery::new(&format!("cursor={}", cursor), []).unwrap();
+        let err = query_error(QueryBuilder::from_str("SELECT * FROM orders", parsed).build());
+        assert_eq!(err.kind, ErrorKind::InvalidCursor);
+
+        // A field that can be NULL
+        let cursor = Cursor::after(["10", "12"]).encode();
+        let query = format!("sort=price-asc-nullslast&cursor={}", cursor);
+
+        let parsed = UrlQuery::new(&query, ["price"]).unwrap();
+        let builder = QueryBuilder::from_str("SELECT * FROM orders", parsed).tiebreaker("id");
+        let err = query_error(builder.build());
+        assert_eq!(err.kind, ErrorKind::InvalidCursor);
+        assert_eq!(err.field.as_deref(), Some("price"));
+
+        // A value that isn't valid for the field's type
+        let schema = Schema::new().field(FieldSpec::new("id", FieldType::Int).sortable(true));
+        let cursor = Cursor::after(["abc"]).encode();
+        let query = format!("sort=id-asc&cursor={}", cursor);
+
+        let parsed = UrlQuery::with_schema(&query, &schema).unwrap();
+        let builder = QueryBuilder::from_str("SELECT * FROM orders", parsed).schema(&schema);
+        let err = query_error(builder.build());
+        assert_eq!(err.kind, ErrorKind::InvalidCursor);
+        assert_eq!(err.field.as_deref(), Some("id"));
+        assert_eq!(err.expected, ["int"]);
+
+        // Only the tiebreaker
+        let cursor = Cursor::after(["12"]).encode();
+        let query = format!("cursor={}&limit=5", cursor);
+
+        let parsed = UrlQuery::new(&query, []).unwrap();
+        let (sql, args) = QueryBuilder::from_str("SELECT * FROM orders", parsed)
+            .tiebreaker("id")
+            .set_database(Database::MsSql)
+            .build()
+            .unwrap();
+        assert_eq!(
+            sql,
+            "SELECT * FROM orders WHERE [id] > @p1 ORDER BY [id] ASC \
+             OFFSET 0 ROWS FETCH NEXT 5 ROWS ONLY"
+        );
+        assert_eq!(args, [Value::String("12".into())]);
     }
 }
@@ -1,144 +1,512 @@
 use std::collections::HashSet;
 
 use crate::{
-    filter::{Condition, Filter},
+    cursor::Cursor,
+    encoding::{decode, encode, encode_field},
+    expr::FilterExpr,
+    filter::{Condition, Filter, FilterValue},
+    having::Having,
+    schema::{self, FieldSpec, FieldType, Schema},
     sort::Sort,
-    ParseError,
+    ErrorKind, ParseError, ParseErrors,
 };
 
-fn check_allowed_fields(field: &str, allowed_fields: &HashSet<&str>) -> Result<(), ParseError> {
-    if !allowed_fields.contains(field) {
-        Err(ParseError::InvalidField)?
+// Returns the sorted names of the fields in the schema that match the predicate
+fn field_names(schema: &Schema, predicate: impl Fn(&FieldSpec) -> bool) -> Vec<String> {
+    let mut names: Vec<String> = schema
+        .fields()
+        .filter(|spec| predicate(spec))
+        .map(|spec| spec.name.clone())
+        .collect();
+    names.sort();
+
+    names
+}
+
+fn check_field<'a>(field: &str, schema: &'a Schema) -> Result<&'a FieldSpec, ParseError> {
+    schema.get(field).ok_or_else(|| {
+        ParseError::new(ErrorKind::InvalidField)
+            .field(field)
+            .expected(field_names(schema, |_| true))
+    })
+}
+
+pub(crate) fn invalid_value(field: &str, field_type: &FieldType) -> ParseError {
+    let err = ParseError::new(ErrorKind::InvalidValue).field(field);
+    match field_type {
+        FieldType::Enum(values) => err.expected(values.iter().cloned()),
+        _ => err.expected([field_type.as_str()]),
+    }
+}
+
+fn check_filter(filter: &Filter, schema: &Schema) -> Result<(), ParseError> {
+    let spec = check_field(&filter.field, schema)?;
+    if !spec.allows(&filter.condition) {
+        let allowed = spec.conditions.iter().flatten().map(|c| c.name());
+        Err(ParseError::new(ErrorKind::ConditionNotAllowed)
+            .field(&filter.field)
+            .expected(allowed))?
+    }
+    if !spec.validate(filter) {
+        Err(invalid_value(&filter.field, &spec.field_type))?
+    }
+
+    Ok(())
+}
+
+fn check_having(having: &Having, schema: &Schema) -> Result<(), ParseError> {
+    let field_type = match having.field {
+        Some(ref field) => Some(&check_field(field, schema)?.field_type),
+        None => None,
+    };
+
+    if let Some(field_type) = having.aggregate.value_type(field_type) {
+        if !schema::validate_filter(field_type, &having.filter) {
+            Err(invalid_value(&having.filter.field, field_type))?
+        }
     }
 
     Ok(())
 }
 
-#[derive(Debug, PartialEq)]
+// Parses a limit or offset. They're written into the SQL, so they have to fit the signed 64-bit
+// integers databases use for them.
+fn parse_bound(value: &str) -> Option<u64> {
+    value.parse().ok().filter(|&bound| bound <= i64::MAX as u64)
+}
+
+// 64-bit FNV-1a, http://www.isthe.com/chongo/tech/comp/fnv/
+fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
+
+/// A parsed url query.
+///
+/// The `filter[]`, `q`, `group`, `having[]`, `sort`, `cursor`, `limit` and `offset` keys are
+/// reserved, so they're never parsed as a `field=value` filter. A field with one of those names,
+/// eg `q`, has to be filtered with `filter[]=q-eq-value`.
+#[derive(Debug, Clone, Default, PartialEq)]
 pub struct UrlQuery {
     pub params: HashSet<String>,
     pub filters: Vec<Filter>,
-    pub group: Option<String>,
-    pub sort: Option<Sort>,
-    pub limit_offset: (Option<String>, Option<String>),
+    pub exprs: Vec<FilterExpr>,
+    pub group: Vec<String>,
+    pub having: Vec<Having>,
+    pub sort: Vec<Sort>,
+    pub cursor: Option<Cursor>,
+    pub limit_offset: (Option<u64>, Option<u64>),
 }
 
 impl UrlQuery {
-    pub fn new<'a>(
-        str: &str,
-        allowed_fields: impl Into<HashSet<&'a str>>,
-    ) -> Result<Self, ParseError> {
-        let allowed_fields: HashSet<&str> = allowed_fields.into();
+    /// Parses a url query, only allowing the fields in the schema. A list of field names can be
+    /// used instead of a [`Schema`] to allow any value and condition for those fields.
+    pub fn new(str: &str, schema: impl Into<Schema>) -> Result<Self, ParseError> {
+        Self::with_schema(str, &schema.into())
+    }
 
-        let mut params = HashSet::new();
+    /// Parses a url query, checking each field against its [`FieldSpec`] in the schema so that an
+    /// invalid value is rejected before the query is built. Errors include the parameter they
+    /// happened in and its position in the query string. Returns the first error, use
+    /// [`parse_all`](Self::parse_all) to get every error.
+    pub fn with_schema(str: &str, schema: &Schema) -> Result<Self, ParseError> {
+        Self::parse_all(str, schema).map_err(|errors| errors.errors.into_iter().next().unwrap())
+    }
 
-        let queries: Vec<&str> = str.split("&").collect();
-        let mut filters = Vec::new();
-        let mut group = None;
-        let mut sort = None;
-        let mut limit_offset = (None, None);
+    /// Parses a url query like [`with_schema`](Self::with_schema), but carries on after an error
+    /// so that every problem with the query is returned, including missing required fields.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use query::{ErrorKind, UrlQuery};
+    ///
+    /// let schema = ["userId", "price"].into();
+    ///
+    /// let errors = UrlQuery::parse_all("userId=1&filter[]=orderId-eq-1&sort=price-up", &schema)
+    ///     .unwrap_err();
+    ///
+    /// let kinds: Vec<ErrorKind> = errors.iter().map(|err| err.kind).collect();
+    /// assert_eq!(kinds, [ErrorKind::InvalidField, ErrorKind::InvalidSortBy]);
+    /// ```
+    pub fn parse_all(str: &str, schema: &Schema) -> Result<Self, ParseErrors> {
+        let (query, errors) = Self::parse_partial(str, schema);
 
-        for q in queries {
-            let (k, v) = match q.split_once("=") {
-                Some(kv) => kv,
+        if !errors.is_empty() {
+            Err(ParseErrors { errors })?
+        }
+
+        Ok(query)
+    }
+
+    // Returns the params that could be parsed along with the errors of the others
+    pub(crate) fn parse_partial(str: &str, schema: &Schema) -> (Self, Vec<ParseError>) {
+        let mut query = Self::default();
+        let mut errors = Vec::new();
+
+        let mut position = 0;
+        for q in str.split('&') {
+            let start = position;
+            position += q.len() + 1;
+
+            let (k, v) = match q.split_once('=') {
+                Some((k, v)) => (decode(k), v),
                 None => continue,
             };
 
-            if k == "filter[]" {
-                let filter = Filter::new(v)?;
-                check_allowed_fields(&filter.field, &allowed_fields)?;
-                filters.push(filter);
-                continue;
+            let mut param_errors = Vec::new();
+            query.parse_param(&k, v, schema, &mut param_errors);
+            errors.extend(param_errors.into_iter().map(|err| err.param(&k, v, start)));
+        }
+
+        for field in field_names(schema, |spec| spec.required) {
+            if !query.filters.iter().any(|filter| filter.field == field) {
+                errors.push(ParseError::new(ErrorKind::Required).field(&field));
             }
+        }
+
+        (query, errors)
+    }
 
-            if k == "group" {
-                check_allowed_fields(v, &allowed_fields)?;
-                group = Some(v.to_owned());
-                continue;
+    fn parse_param(&mut self, k: &str, v: &str, schema: &Schema, errors: &mut Vec<ParseError>) {
+        // Filters, filter expressions, groups and sorts are decoded after they've been split on their delimiters:
+        if k == "filter[]" {
+            match Filter::new(v).and_then(|filter| {
+                check_filter(&filter, schema)?;
+                Ok(filter)
+            }) {
+                Ok(filter) => self.filters.push(filter),
+                Err(err) => errors.push(err),
             }
+            return;
+        }
+
+        if k == "q" {
+            let expr = match FilterExpr::new(v) {
+                Ok(expr) => expr,
+                Err(err) => return errors.push(err),
+            };
 
-            if k == "sort" {
-                sort = Some(Sort::new(v)?);
-                check_allowed_fields(&sort.as_ref().unwrap().field, &allowed_fields)?;
-                continue;
+            let len = errors.len();
+            for filter in expr.filters() {
+                if let Err(err) = check_filter(filter, schema) {
+                    errors.push(err);
+                }
+            }
+            if errors.len() == len {
+                self.exprs.push(expr);
             }
+            return;
+        }
 
-            if k == "limit" {
-                limit_offset.0 = Some(v.to_owned());
-                continue;
+        // group=userId,status
+        if k == "group" {
+            for v in v.split(',').map(decode) {
+                match check_field(&v, schema) {
+                    Ok(spec) if spec.groupable => self.group.push(v),
+                    Ok(_) => errors.push(
+                        ParseError::new(ErrorKind::FieldNotGroupable)
+                            .field(&v)
+                            .expected(field_names(schema, |spec| spec.groupable)),
+                    ),
+                    Err(err) => errors.push(err),
+                }
             }
+            return;
+        }
 
-            if k == "offset" {
-                limit_offset.1 = Some(v.to_owned());
-                continue;
+        if k == "having[]" {
+            match Having::new(v).and_then(|having| {
+                check_having(&having, schema)?;
+                Ok(having)
+            }) {
+                Ok(having) => self.having.push(having),
+                Err(err) => errors.push(err),
             }
+            return;
+        }
 
-            check_allowed_fields(k, &allowed_fields)?;
-            filters.push(Filter::from_key_value(k, v, Condition::EQ));
+        // sort=price-desc,createdAt-asc
+        if k == "sort" {
+            for v in v.split(',') {
+                let sort = match Sort::new(v) {
+                    Ok(sort) => sort,
+                    Err(err) => {
+                        errors.push(err);
+                        continue;
+                    }
+                };
 
-            // To check required:
-            params.insert(k.into());
+                match check_field(&sort.field, schema) {
+                    Ok(spec) if spec.sortable => self.sort.push(sort),
+                    Ok(_) => errors.push(
+                        ParseError::new(ErrorKind::FieldNotSortable)
+                            .field(&sort.field)
+                            .expected(field_names(schema, |spec| spec.sortable)),
+                    ),
+                    Err(err) => errors.push(err),
+                }
+            }
+            return;
         }
 
-        Ok(Self {
-            params,
-            filters,
-            group,
-            sort,
-            limit_offset,
-        })
+        let v = decode(v);
+
+        if k == "cursor" {
+            match Cursor::new(&v) {
+                Ok(cursor) => self.cursor = Some(cursor),
+                Err(err) => errors.push(err),
+            }
+            return;
+        }
+
+        if k == "limit" {
+            match parse_bound(&v) {
+                Some(limit) => self.limit_offset.0 = Some(limit),
+                None => errors.push(ErrorKind::InvalidLimit.into()),
+            }
+            return;
+        }
+
+        if k == "offset" {
+            match parse_bound(&v) {
+                Some(offset) => self.limit_offset.1 = Some(offset),
+                None => errors.push(ErrorKind::InvalidOffset.into()),
+            }
+            return;
+        }
+
+        let filter = Filter::from_key_value(k, &v, Condition::EQ);
+        if let Err(err) = check_filter(&filter, schema) {
+            return errors.push(err);
+        }
+        self.filters.push(filter);
+
+        // To check required:
+        self.params.insert(k.to_owned());
     }
 
-    pub fn check_required<'a, T>(&self, required: T) -> Result<(), String>
+    pub fn check_required<'a, T>(&self, required: T) -> Result<(), ParseError>
     where
         T: IntoIterator<Item = &'a str>,
     {
         for r in required {
-            if let None = self.params.get(r) {
-                let mut res = String::new();
-                res.push_str(r);
-                res.push_str(" is required");
-                Err(res)?
+            if !self.params.contains(r) {
+                Err(ParseError::new(ErrorKind::Required).field(r))?
             };
         }
 
         Ok(())
     }
 
-    pub fn check_limit(&self) -> Result<&str, String> {
+    pub fn check_limit(&self) -> Result<u64, ParseError> {
         match self.limit_offset.0 {
-            Some(ref limit) => Ok(limit),
-            None => Err(String::from("limit is required")),
+            Some(limit) => Ok(limit),
+            None => Err(ParseError::new(ErrorKind::Required).field("limit")),
         }
     }
 
-    pub fn check_offset(&self) -> Result<&str, String> {
+    pub fn check_offset(&self) -> Result<u64, ParseError> {
         match self.limit_offset.1 {
-            Some(ref offset) => Ok(offset),
-            None => Err(String::from("offset is required")),
+            Some(offset) => Ok(offset),
+            None => Err(ParseError::new(ErrorKind::Required).field("offset")),
         }
     }
 
-    pub fn check_limit_and_offset(&self) -> Result<(&str, &str), String> {
+    pub fn check_limit_and_offset(&self) -> Result<(u64, u64), ParseError> {
         let limit = self.check_limit()?;
         let offset = self.check_offset()?;
 
         Ok((limit, offset))
     }
 
+    /// Returns the url query as a query string that parses back into the same UrlQuery, eg to link
+    /// to the next page after changing the offset with [`limit_offset_mut`](Self::limit_offset_mut).
+    /// Every part is percent-encoded and the params are written in a fixed order: filters, `q`,
+    /// `group`, `having[]`, `sort`, `cursor`, `limit` and `offset`. An `eq` filter of a field that
+    /// was given as a plain param is written as one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use query::UrlQuery;
+    ///
+    /// let mut parsed = UrlQuery::new(
+    ///     "limit=10&userName=bob%20smith&sort=createdAt-desc&filter[]=status-in-a,b",
+    ///     ["userName", "status", "createdAt"],
+    /// )
+    /// .unwrap();
+    ///
+    /// parsed.limit_offset_mut().1 = Some(10);
+    ///
+    /// assert_eq!(
+    ///     parsed.to_query_string(),
+    ///     "userName=bob%20smith&filter[]=status-in-a,b&sort=createdAt-desc&limit=10&offset=10"
+    /// );
+    /// ```
+    pub fn to_query_string(&self) -> String {
+        let mut params = Vec::new();
+
+        for filter in &self.filters {
+            match filter.value {
+                FilterValue::Single(ref value)
+                    if filter.condition == Condition::EQ && self.params.contains(&filter.field) =>
+                {
+                    params.push(format!("{}={}", encode(&filter.field), encode(value)));
+                }
+                _ => params.push(format!("filter[]={}", filter.to_query())),
+            }
+        }
+
+        for expr in &self.exprs {
+            params.push(format!("q={}", expr.to_query()));
+        }
+
+        if !self.group.is_empty() {
+            let group: Vec<String> = self.group.iter().map(|g| encode_field(g)).collect();
+            params.push(format!("group={}", group.join(",")));
+        }
+
+        for having in &self.having {
+            params.push(format!("having[]={}", having.to_query()));
+        }
+
+        if !self.sort.is_empty() {
+            let sort: Vec<String> = self.sort.iter().map(|s| s.to_query()).collect();
+            params.push(format!("sort={}", sort.join(",")));
+        }
+
+        if let Some(ref cursor) = self.cursor {
+            params.push(format!("cursor={}", cursor.encode()));
+        }
+
+        if let Some(limit) = self.limit_offset.0 {
+            params.push(format!("limit={}", limit));
+        }
+
+        if let Some(offset) = self.limit_offset.1 {
+            params.push(format!("offset={}", offset));
+        }
+
+        params.join("&")
+    }
+
+    /// Returns an equivalent url query in a canonical form, so that queries that only differ in
+    /// the order or repetition of their params have the same [`to_query_string`]. Filters, filter
+    /// expressions, groups and having filters are sorted and deduplicated, later sorts by an already
+    /// sorted field are removed and an offset of 0 is removed. Every filter is written as a
+    /// `filter[]` param, so the normalized query has no plain params for
+    /// [`check_required`](Self::check_required).
+    ///
+    /// [`to_query_string`]: Self::to_query_string
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use query::UrlQuery;
+    ///
+    /// let a = UrlQuery::new("b=2&a=1&filter[]=c-in-y,x,y", ["a", "b", "c"]).unwrap();
+    /// let b = UrlQuery::new("filter[]=c-in-x,y&a=1&b=2&a=1&offset=0", ["a", "b", "c"]).unwrap();
+    ///
+    /// assert_eq!(
+    ///     a.normalized().to_query_string(),
+    ///     "filter[]=a-eq-1&filter[]=b-eq-2&filter[]=c-in-x,y"
+    /// );
+    /// assert_eq!(a.normalized(), b.normalized());
+    /// assert_eq!(a.stable_hash(), b.stable_hash());
+    /// ```
+    pub fn normalized(&self) -> Self {
+        let mut filters: Vec<Filter> = self.filters.iter().map(Filter::normalized).collect();
+        let mut exprs = Vec::new();
+
+        // Top level expressions are ANDed with the filters
+        for expr in &self.exprs {
+            match expr.normalized() {
+                FilterExpr::Filter(filter) => filters.push(filter),
+                FilterExpr::And(and) => {
+                    for expr in and {
+                        match expr {
+                            FilterExpr::Filter(filter) => filters.push(filter),
+                            expr => exprs.push(expr),
+                        }
+                    }
+                }
+                expr => exprs.push(expr),
+            }
+        }
+
+        filters.sort_by_cached_key(Filter::to_query);
+        filters.dedup();
+        exprs.sort_by_cached_key(FilterExpr::to_query);
+        exprs.dedup();
+
+        let mut group = self.group.clone();
+        group.sort();
+        group.dedup();
+
+        let mut having: Vec<Having> = self.having.iter().map(Having::normalized).collect();
+        having.sort_by_cached_key(Having::to_query);
+        having.dedup();
+
+        // Only the first sort by a field has an effect
+        let mut sort: Vec<Sort> = Vec::new();
+        for s in &self.sort {
+            if !sort.iter().any(|sort| sort.field == s.field) {
+                sort.push(s.clone());
+            }
+        }
+
+        let (limit, offset) = self.limit_offset;
+
+        Self {
+            params: HashSet::new(),
+            filters,
+            exprs,
+            group,
+            having,
+            sort,
+            cursor: self.cursor.clone(),
+            limit_offset: (limit, offset.filter(|&offset| offset != 0)),
+        }
+    }
+
+    /// Returns a 64-bit FNV-1a hash of the [`normalized`](Self::normalized) query string, eg to use
+    /// as a cache key or ETag. Unlike [`std::hash::Hash`], the hash is the same across runs,
+    /// platforms and versions of Rust.
+    pub fn stable_hash(&self) -> u64 {
+        fnv1a(self.normalized().to_query_string().as_bytes())
+    }
+
     pub fn filters_mut(&mut self) -> &mut Vec<Filter> {
         &mut self.filters
     }
 
-    pub fn group_mut(&mut self) -> &mut Option<String> {
+    pub fn exprs_mut(&mut self) -> &mut Vec<FilterExpr> {
+        &mut self.exprs
+    }
+
+    pub fn group_mut(&mut self) -> &mut Vec<String> {
         &mut self.group
     }
 
-    pub fn sort_mut(&mut self) -> &mut Option<Sort> {
+    pub fn having_mut(&mut self) -> &mut Vec<Having> {
+        &mut self.having
+    }
+
+    pub fn sort_mut(&mut self) -> &mut Vec<Sort> {
         &mut self.sort
     }
 
-    pub fn limit_offset_mut(&mut self) -> &mut (Option<String>, Option<String>) {
+    pub fn cursor_mut(&mut self) -> &mut Option<Cursor> {
+        &mut self.cursor
+    }
+
+    pub fn limit_offset_mut(&mut self) -> &mut (Option<u64>, Option<u64>) {
         &mut self.limit_offset
     }
 }
@@ -147,12 +515,20 @@ impl UrlQuery {
 mod tests {
     use std::collections::HashSet;
 
+    use proptest::prelude::*;
+
     use crate::{
-        filter::{Condition, Filter},
-        sort::{Sort, SortBy},
-        ParseError, UrlQuery,
+        cursor::Cursor,
+        expr::FilterExpr,
+        filter::{Condition, Filter, FilterValue},
+        having::{Aggregate, Having},
+        schema::{FieldSpec, FieldType, Schema},
+        sort::{Nulls, Sort, SortBy},
+        ErrorKind, ParseError, UrlQuery,
     };
 
+    use super::fnv1a;
+
     #[test]
     fn test_parse_query() {
         let query =
@@ -182,11 +558,15 @@ mod tests {
                     value: "200".into(),
                 },
             ],
-            group: Some(String::from("orderId")),
-            sort: Some(Sort {
+            exprs: vec![],
+            group: vec![String::from("orderId")],
+            having: vec![],
+            sort: vec![Sort {
                 field: String::from("price"),
                 sort_by: SortBy::DESC,
-            }),
+                nulls: None,
+            }],
+            cursor: None,
             limit_offset: (None, None),
         };
 
@@ -202,8 +582,11 @@ mod tests {
         let expected = UrlQuery {
             params: HashSet::default(),
             filters: vec![],
-            group: None,
-            sort: None,
+            exprs: vec![],
+            group: vec![],
+            having: vec![],
+            sort: vec![],
+            cursor: None,
             limit_offset: (None, None),
         };
 
@@ -219,15 +602,37 @@ mod tests {
         let expected = UrlQuery {
             params: HashSet::default(),
             filters: vec![],
-            group: None,
-            sort: None,
-            limit_offset: (Some("10".into()), Some("0".into())),
+            exprs: vec![],
+            group: vec![],
+            having: vec![],
+            sort: vec![],
+            cursor: None,
+            limit_offset: (Some(10), Some(0)),
         };
 
         assert_eq!(parsed, expected);
         assert!(parsed.check_limit_and_offset().is_ok());
     }
 
+    #[test]
+    fn test_parse_query_invalid_limit_offset() {
+        let result = UrlQuery::new("limit=1;DROP TABLE orders", []);
+        assert_eq!(result.map_err(|e| e.kind), Err(ErrorKind::InvalidLimit));
+
+        let result = UrlQuery::new("limit=10&offset=-1", []);
+        assert_eq!(result.map_err(|e| e.kind), Err(ErrorKind::InvalidOffset));
+
+        // Out of range for a bigint
+        let result = UrlQuery::new("limit=18446744073709551615", []);
+        assert_eq!(result.map_err(|e| e.kind), Err(ErrorKind::InvalidLimit));
+
+        let result = UrlQuery::new("offset=9223372036854775808", []);
+        assert_eq!(result.map_err(|e| e.kind), Err(ErrorKind::InvalidOffset));
+
+        let parsed = UrlQuery::new("limit=9223372036854775807", []).unwrap();
+        assert_eq!(parsed.limit_offset.0, Some(i64::MAX as u64));
+    }
+
     #[test]
     fn test_required() {
         let query = "userId=bob&filter[]=orderId-eq-1&filter[]=price-ge-200&sort=price-desc";
@@ -238,7 +643,76 @@ mod tests {
         assert!(v1.is_ok());
 
         let v1 = parsed.check_required(["userId", "limit", "offset"]);
-        assert!(v1.is_err());
+        let err = v1.unwrap_err();
+        assert_eq!(err.kind, ErrorKind::Required);
+        assert_eq!(err.to_string(), "limit is required");
+
+        let err = parsed.check_limit().unwrap_err();
+        assert_eq!(err.field.as_deref(), Some("limit"));
+    }
+
+    #[test]
+    fn test_parse_query_percent_encoded() {
+        let query =
+            "userName=bob%20smith&email=bob%40example.com&filter%5B%5D=name-eq-caf%C3%A9+au+lait";
+
+        let parsed = UrlQuery::new(query, ["userName", "email", "name"]).unwrap();
+
+        let values: Vec<String> = parsed.filters.iter().map(|f| f.value.to_string()).collect();
+        assert_eq!(values, ["bob smith", "bob@example.com", "café au lait"]);
+        assert!(parsed.check_required(["userName", "email"]).is_ok());
+    }
+
+    #[test]
+    fn test_parse_query_exprs() {
+        let query = "q=or(status-eq-a;price-gt-10)&q=not(userId-eq-1)";
+
+        let parsed = UrlQuery::new(query, ["status", "price", "userId"]).unwrap();
+        assert_eq!(parsed.exprs.len(), 2);
+
+        let result = UrlQuery::new(query, ["status", "price"]);
+        assert_eq!(result.map_err(|e| e.kind), Err(ErrorKind::InvalidField));
+
+        // q is reserved, a field named q is filtered with filter[]
+        let parsed = UrlQuery::new("filter[]=q-eq-abc", ["q"]).unwrap();
+        assert_eq!(parsed.filters[0].field, "q");
+        assert!(UrlQuery::new("q=abc", ["q"]).is_err());
+
+        // Deeply nested groups are rejected instead of overflowing the stack
+        let query = format!(
+            "q={}status-eq-a{}",
+            "not(".repeat(10_000),
+            ")".repeat(10_000)
+        );
+        let result = UrlQuery::new(&query, ["status"]);
+        assert_eq!(result.map_err(|e| e.kind), Err(ErrorKind::InvalidFilter));
+    }
+
+    #[test]
+    fn test_parse_query_multiple_sorts() {
+        let query = "sort=price-desc,createdAt-asc&sort=id-asc";
+
+        let parsed = UrlQuery::new(query, ["price", "createdAt", "id"]).unwrap();
+
+        let sort: Vec<String> = parsed.sort.iter().map(|s| s.to_string()).collect();
+        assert_eq!(sort, ["price DESC", "createdAt ASC", "id ASC"]);
+
+        assert_eq!(
+            UrlQuery::new("sort=price-desc,", ["price"]).map_err(|e| e.kind),
+            Err(ErrorKind::InvalidSort)
+        );
+    }
+
+    #[test]
+    fn test_parse_query_group_having() {
+        let query = "group=userId,status&having[]=count-gt-5&having[]=sum(price)-ge-100";
+
+        let parsed = UrlQuery::new(query, ["userId", "status", "price"]).unwrap();
+        assert_eq!(parsed.group, ["userId", "status"]);
+        assert_eq!(parsed.having.len(), 2);
+
+        let result = UrlQuery::new(query, ["userId", "status"]);
+        assert_eq!(result.map_err(|e| e.kind), Err(ErrorKind::InvalidField));
     }
 
     #[test]
@@ -247,6 +721,360 @@ mod tests {
 
         let result = UrlQuery::new(query, ["userId"]);
 
-        assert_eq!(result, Err(ParseError::InvalidField))
+        let expected = ParseError {
+            kind: ErrorKind::InvalidField,
+            key: Some("filter[]".into()),
+            value: Some("orderId-eq-1".into()),
+            position: Some(11),
+            field: Some("orderId".into()),
+            expected: vec!["userId".into()],
+        };
+        assert_eq!(result, Err(expected));
+    }
+
+    #[test]
+    fn test_parse_query_schema() {
+        let schema = Schema::new()
+            .field(FieldSpec::new("userId", FieldType::Uuid))
+            .field(
+                FieldSpec::new("price", FieldType::Float)
+                    .conditions([Condition::GE, Condition::LE, Condition::BETWEEN])
+                    .sortable(true),
+            )
+            .field(
+                FieldSpec::new(
+                    "status",
+                    FieldType::Enum(vec!["pending".into(), "shipped".into()]),
+                )
+                .groupable(true),
+            );
+
+        let query = "userId=8bd8a6fb-e2b2-47ab-b3db-4f47c067ba5e&filter[]=price-between-10,20.5\
+                     &filter[]=status-in-pending,shipped&sort=price-desc&group=status\
+                     &having[]=count-gt-5&having[]=avg(price)-ge-9.5";
+        assert!(UrlQuery::with_schema(query, &schema).is_ok());
+
+        for (query, kind, field) in [
+            ("filter[]=price-ge-abc", ErrorKind::InvalidValue, "price"),
+            ("userId=1", ErrorKind::InvalidValue, "userId"),
+            (
+                "filter[]=status-in-pending,lost",
+                ErrorKind::InvalidValue,
+                "status",
+            ),
+            (
+                "q=or(price-ge-1;price-le-x)",
+                ErrorKind::InvalidValue,
+                "price",
+            ),
+            ("having[]=count-gt-1.5", ErrorKind::InvalidValue, "count"),
+            (
+                "filter[]=price-eq-1",
+                ErrorKind::ConditionNotAllowed,
+                "price",
+            ),
+            ("sort=status-asc", ErrorKind::FieldNotSortable, "status"),
+            ("group=price", ErrorKind::FieldNotGroupable, "price"),
+            ("filter[]=orderId-eq-1", ErrorKind::InvalidField, "orderId"),
+        ] {
+            let err = UrlQuery::with_schema(query, &schema).unwrap_err();
+            assert_eq!(
+                (err.kind, err.field.as_deref()),
+                (kind, Some(field)),
+                "{}",
+                query
+            );
+        }
+
+        let err = UrlQuery::with_schema("limit=5&filter[]=price-eq-1", &schema).unwrap_err();
+        assert_eq!(err.expected, ["ge", "le", "between"]);
+        assert_eq!(
+            err.to_string(),
+            "condition not allowed price in filter[]=price-eq-1 at position 8, \
+             expected one of: ge, le, between"
+        );
+    }
+
+    #[test]
+    fn test_parse_all() {
+        let schema = Schema::new()
+            .field(FieldSpec::new("userId", FieldType::Int).required(true))
+            .field(FieldSpec::new("price", FieldType::Float).sortable(true))
+            .field(FieldSpec::new("status", FieldType::String));
+
+        let query = "filter[]=price-gt-abc&filter[]=orderId-eq-1&sort=price-desc,status-asc,id-up\
+                     &q=or(price-zz-1;status-eq-a)&limit=ten";
+
+        let errors = UrlQuery::parse_all(query, &schema).unwrap_err();
+
+        let kinds: Vec<(ErrorKind, Option<&str>)> = errors
+            .iter()
+            .map(|err| (err.kind, err.key.as_deref()))
+            .collect();
+        assert_eq!(
+            kinds,
+            [
+                (ErrorKind::InvalidValue, Some("filter[]")),
+                (ErrorKind::InvalidField, Some("filter[]")),
+                (ErrorKind::FieldNotSortable, Some("sort")),
+                (ErrorKind::InvalidSortBy, Some("sort")),
+                (ErrorKind::InvalidCondition, Some("q")),
+                (ErrorKind::InvalidLimit, Some("limit")),
+                (ErrorKind::Required, None),
+            ]
+        );
+
+        // Fails on the first error
+        let err = UrlQuery::with_schema(query, &schema).unwrap_err();
+        assert_eq!(err, errors.errors[0]);
+
+        assert!(UrlQuery::parse_all("userId=1", &schema).is_ok());
+    }
+
+    #[test]
+    fn test_to_query_string() {
+        let query = "userId=1&filter[]=created%2Dat-between-2024-01-01,2024-02-01\
+                     &q=or(status-eq-a%3Bb;not(price-gt-10))&group=status\
+                     &having[]=sum(price)-ge-100&sort=price-desc-nullslast&offset=20";
+
+        let parsed = UrlQuery::new(query, ["userId", "created-at", "status", "price"]).unwrap();
+        assert_eq!(parsed.to_query_string(), query);
+
+        let mut parsed = UrlQuery::new("filter[]=userId-eq-1", ["userId"]).unwrap();
+        parsed.filters_mut()[0].value = "bob & alice".into();
+        assert_eq!(
+            parsed.to_query_string(),
+            "filter[]=userId-eq-bob%20%26%20alice"
+        );
+    }
+
+    #[test]
+    fn test_normalized() {
+        let fields = ["userId", "status", "price"];
+
+        let a = UrlQuery::new(
+            "userId=1&q=and(price-gt-1;or(status-eq-b;status-eq-a;or(status-eq-a)))\
+             &sort=price-desc,userId-asc,price-asc&group=status,userId&limit=10",
+            fields,
+        )
+        .unwrap();
+        let b = UrlQuery::new(
+            "filter[]=price-gt-1&group=userId,status&filter[]=userId-in-1\
+             &q=not(not(or(status-eq-a;status-eq-b)))&limit=10&offset=0&sort=price-desc,userId-asc",
+            fields,
+        )
+        .unwrap();
+
+        let expected = "filter[]=price-gt-1&filter[]=userId-eq-1&q=or(status-eq-a;status-eq-b)\
+                        &group=status,userId&sort=price-desc,userId-asc&limit=10";
+        assert_eq!(a.normalized().to_query_string(), expected);
+        assert_eq!(b.normalized().to_query_string(), expected);
+        assert_eq!(a.stable_hash(), b.stable_hash());
+
+        let c = UrlQuery::new("userId=2", fields).unwrap();
+        assert_ne!(a.stable_hash(), c.stable_hash());
+    }
+
+    #[test]
+    fn test_fnv1a() {
+        assert_eq!(fnv1a(b""), 0xcbf29ce484222325);
+        assert_eq!(fnv1a(b"a"), 0xaf63dc4c8601ec8c);
+        assert_eq!(fnv1a(b"foobar"), 0x85944171f73967e8);
+    }
+
+    fn condition() -> impl Strategy<Value = Condition> {
+        prop::sample::select(vec![
+            Condition::EQ,
+            Condition::NE,
+            Condition::GT,
+            Condition::GE,
+            Condition::LT,
+            Condition::LE,
+            Condition::IN,
+            Condition::NIN,
+            Condition::LIKE,
+            Condition::ILIKE,
+            Condition::SW,
+            Condition::EW,
+            Condition::CT,
+            Condition::NULL,
+            Condition::NOTNULL,
+            Condition::BETWEEN,
+        ])
+    }
+
+    // A filter of `field` with a value that fits its condition
+    fn filter(
+        field: impl Strategy<Value = String>,
+        value: impl Strategy<Value = String> + Clone,
+    ) -> impl Strategy<Value = Filter> {
+        let non_empty = value.clone().prop_filter("empty", |v| !v.is_empty());
+
+        (
+            field,
+            condition(),
+            value,
+            prop::collection::vec(non_empty, 1..4),
+        )
+            .prop_map(|(field, condition, value, mut values)| {
+                let value = match condition {
+                    Condition::NULL | Condition::NOTNULL => FilterValue::None,
+                    Condition::IN | Condition::NIN => FilterValue::List(values),
+                    Condition::BETWEEN => {
+                        let from = values.remove(0);
+                        FilterValue::Range(from.clone(), values.pop().unwrap_or(from))
+                    }
+                    _ => FilterValue::Single(value),
+                };
+
+                Filter {
+                    field,
+                    condition,
+                    value,
+                }
+            })
+    }
+
+    fn expr() -> impl Strategy<Value = FilterExpr> {
+        let leaf = filter(any::<String>(), any::<String>()).prop_map(FilterExpr::Filter);
+
+        leaf.prop_recursive(3, 16, 3, |inner| {
+            prop_oneof![
+                prop::collection::vec(inner.clone(), 1..4).prop_map(FilterExpr::And),
+                prop::collection::vec(inner.clone(), 1..4).prop_map(FilterExpr::Or),
+                inner.prop_map(|expr| FilterExpr::Not(Box::new(expr))),
+            ]
+        })
+    }
+
+    fn having() -> impl Strategy<Value = Having> {
+        let aggregate = prop::sample::select(vec!["count", "sum", "avg", "min", "max"]);
+
+        // Counts and averages have to be numbers
+        let value = any::<i64>().prop_map(|v| v.to_string());
+        let field = any::<String>().prop_filter("empty", |f| !f.is_empty());
+
+        (
+            aggregate,
+            any::<bool>(),
+            field,
+            filter(Just(String::new()), value),
+        )
+            .prop_map(|(aggregate, count_all, field, filter)| {
+                // Only count can leave out the field
+                let field = match aggregate {
+                    "count" if count_all => None,
+                    _ => Some(field),
+                };
+
+                let filter = Filter {
+                    field: match field {
+                        Some(ref field) => format!("{}({})", aggregate, field),
+                        None => String::from("count"),
+                    },
+                    ..filter
+                };
+
+                Having {
+                    aggregate: aggregate.parse::<Aggregate>().unwrap(),
+                    field,
+                    filter,
+                }
+            })
+    }
+
+    fn sort() -> impl Strategy<Value = Sort> {
+        (any::<String>(), any::<bool>(), any::<Option<bool>>()).prop_map(
+            |(field, desc, nulls_last)| Sort {
+                field,
+                sort_by: if desc { SortBy::DESC } else { SortBy::ASC },
+                nulls: nulls_last.map(|last| if last { Nulls::LAST } else { Nulls::FIRST }),
+            },
+        )
+    }
+
+    fn cursor() -> impl Strategy<Value = Cursor> {
+        (any::<bool>(), prop::collection::vec(any::<String>(), 1..4)).prop_map(|(after, values)| {
+            if after {
+                Cursor::after(values)
+            } else {
+                Cursor::before(values)
+            }
+        })
+    }
+
+    fn url_query() -> impl Strategy<Value = UrlQuery> {
+        let reserved = [
+            "filter[]", "q", "group", "having[]", "sort", "cursor", "limit", "offset",
+        ];
+        let param = any::<String>().prop_filter("reserved", move |k| !reserved.contains(&&**k));
+
+        // A plain param or a filter[]
+        let filter = prop_oneof![
+            (param, any::<String>())
+                .prop_map(|(k, v)| (true, Filter::from_key_value(&k, &v, Condition::EQ))),
+            filter(any::<String>(), any::<String>()).prop_map(|filter| (false, filter)),
+        ];
+
+        (
+            prop::collection::vec(filter, 0..4),
+            prop::collection::vec(expr(), 0..3),
+            prop::collection::vec(any::<String>(), 0..3),
+            prop::collection::vec(having(), 0..3),
+            prop::collection::vec(sort(), 0..3),
+            prop::option::of(cursor()),
+            (
+                prop::option::of(0..=i64::MAX as u64),
+                prop::option::of(0..=i64::MAX as u64),
+            ),
+        )
+            .prop_map(
+                |(filters, exprs, group, having, sort, cursor, limit_offset)| {
+                    let params = filters
+                        .iter()
+                        .filter(|(param, _)| *param)
+                        .map(|(_, filter)| filter.field.clone())
+                        .collect();
+
+                    UrlQuery {
+                        params,
+                        filters: filters.into_iter().map(|(_, filter)| filter).collect(),
+                        exprs,
+                        group,
+                        having,
+                        sort,
+                        cursor,
+                        limit_offset,
+                    }
+                },
+            )
+    }
+
+    proptest! {
+        #[test]
+        fn test_to_query_string_round_trip(query in url_query()) {
+            let mut fields: Vec<&str> = query.filters.iter().map(|f| f.field.as_str()).collect();
+            fields.extend(query.exprs.iter().flat_map(|e| e.filters()).map(|f| f.field.as_str()));
+            fields.extend(query.group.iter().map(String::as_str));
+            fields.extend(query.having.iter().filter_map(|h| h.field.as_deref()));
+            fields.extend(query.sort.iter().map(|s| s.field.as_str()));
+
+            let parsed = UrlQuery::new(&query.to_query_string(), fields);
+            prop_assert_eq!(parsed, Ok(query));
+        }
+
+        #[test]
+        fn test_normalized_order(query in url_query()) {
+            let normalized = query.normalized();
+            prop_assert_eq!(normalized.normalized(), normalized.clone());
+
+            let mut reversed = query.clone();
+            reversed.filters.reverse();
+            reversed.exprs.reverse();
+            reversed.group.reverse();
+            reversed.having.reverse();
+            prop_assert_eq!(reversed.normalized(), normalized);
+            prop_assert_eq!(reversed.stable_hash(), query.stable_hash());
+        }
     }
 }
@@ -0,0 +1,162 @@
+use crate::schema::FieldType;
+
+/// A value to bind to a placeholder. Uuids, dates and timestamps are validated and kept as text in a
+/// canonical form, so their variant tells a binder which type to bind them as and the text can be
+/// parsed into whichever types the database driver uses for them.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Bool(bool),
+    I64(i64),
+    F64(f64),
+    String(String),
+    /// A lowercase hyphenated uuid, eg `8bd8a6fb-e2b2-47ab-b3db-4f47c067ba5e`.
+    Uuid(String),
+    /// A `YYYY-MM-DD` date.
+    Date(String),
+    /// A `YYYY-MM-DDTHH:MM:SS[.fff]` timestamp, followed by `Z` or a `+HH:MM`/`-HH:MM` offset
+    /// unless it has no time zone.
+    Timestamp(String),
+    /// The values of an `in` or `nin` filter bound as a single array.
+    List(Vec<Value>),
+}
+
+impl Value {
+    /// Parses the value as `field_type`, returning `None` if it isn't a valid value of that type.
+    pub fn parse(field_type: &FieldType, value: &str) -> Option<Self> {
+        if !field_type.validate(value) {
+            return None;
+        }
+
+        let value = match field_type {
+            FieldType::Int => Value::I64(value.parse().ok()?),
+            FieldType::Float => Value::F64(value.parse().ok()?),
+            FieldType::Bool => Value::Bool(value == "true"),
+            FieldType::Uuid => Value::Uuid(value.to_ascii_lowercase()),
+            FieldType::Date => Value::Date(value.into()),
+            FieldType::Timestamp => Value::Timestamp(normalize_timestamp(value)),
+            FieldType::String | FieldType::Enum(_) => Value::String(value.into()),
+        };
+
+        Some(value)
+    }
+
+    /// Parses the value as `field_type`, or returns it as a string when there's no type. The value
+    /// has to have been validated against the type, eg while parsing the url query with a schema.
+    pub(crate) fn parse_or_string(field_type: Option<&FieldType>, value: &str) -> Self {
+        let parsed = field_type.and_then(|field_type| Value::parse(field_type, value));
+        debug_assert!(
+            parsed.is_some() || field_type.is_none(),
+            "{} isn't a valid {:?}",
+            value,
+            field_type
+        );
+
+        parsed.unwrap_or_else(|| Value::String(value.into()))
+    }
+}
+
+// Uses a `T` between the date and time and an uppercase `Z`, the timestamp has been validated
+fn normalize_timestamp(value: &str) -> String {
+    let (date, rest) = value.split_at(10);
+
+    let mut timestamp = String::with_capacity(value.len());
+    timestamp.push_str(date);
+    timestamp.push('T');
+    timestamp.push_str(&rest[1..]);
+    if timestamp.ends_with('z') {
+        timestamp.pop();
+        timestamp.push('Z');
+    }
+
+    timestamp
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<i64> for Value {
+    fn from(value: i64) -> Self {
+        Value::I64(value)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(value: f64) -> Self {
+        Value::F64(value)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(value: &str) -> Self {
+        Value::String(value.into())
+    }
+}
+
+impl From<String> for Value {
+    fn from(value: String) -> Self {
+        Value::String(value)
+    }
+}
+
+impl<T: Into<Value>> From<Option<T>> for Value {
+    fn from(value: Option<T>) -> Self {
+        match value {
+            Some(value) => value.into(),
+            None => Value::Null,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::schema::FieldType;
+
+    use super::Value;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Value::parse(&FieldType::Int, "-5"), Some(Value::I64(-5)));
+        assert_eq!(
+            Value::parse(&FieldType::Float, "1.5"),
+            Some(Value::F64(1.5))
+        );
+        assert_eq!(
+            Value::parse(&FieldType::Bool, "false"),
+            Some(Value::Bool(false))
+        );
+        assert_eq!(
+            Value::parse(&FieldType::Date, "2024-01-31"),
+            Some(Value::Date("2024-01-31".into()))
+        );
+        assert_eq!(Value::parse(&FieldType::Int, "abc"), None);
+        assert_eq!(Value::parse(&FieldType::Float, "NaN"), None);
+
+        // Uuids and timestamps are normalized
+        assert_eq!(
+            Value::parse(&FieldType::Uuid, "8BD8A6FB-E2B2-47AB-B3DB-4F47C067BA5E"),
+            Some(Value::Uuid("8bd8a6fb-e2b2-47ab-b3db-4f47c067ba5e".into()))
+        );
+        assert_eq!(
+            Value::parse(&FieldType::Timestamp, "2024-01-31 12:00:00.5z"),
+            Some(Value::Timestamp("2024-01-31T12:00:00.5Z".into()))
+        );
+        assert_eq!(
+            Value::parse(&FieldType::Timestamp, "2024-01-31t12:00:00-05:00"),
+            Some(Value::Timestamp("2024-01-31T12:00:00-05:00".into()))
+        );
+        assert_eq!(
+            Value::parse(&FieldType::Timestamp, "2024-01-31T12:00:00"),
+            Some(Value::Timestamp("2024-01-31T12:00:00".into()))
+        );
+
+        assert_eq!(
+            Value::parse_or_string(Some(&FieldType::Int), "5"),
+            Value::I64(5)
+        );
+        assert_eq!(Value::parse_or_string(None, "5"), Value::String("5".into()));
+    }
+}